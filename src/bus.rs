@@ -0,0 +1,384 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use crate::ingest::SubscriptionStatus;
+use crate::launches::{BondingCurveEvent, MigrationEvent};
+use crate::parsing::{InstructionError, PoolCreationEvent};
+use crate::store::EventStore;
+
+// How many events a slow client may fall behind before it starts skipping
+const BUS_CAPACITY: usize = 256;
+// How many recent events are kept for clients resuming with Last-Event-ID
+const REPLAY_CAPACITY: usize = 1_000;
+// Ids are reserved in the event store this many at a time, so after a restart
+// numbering resumes past every id that may have been sent, whatever its type.
+// The next block is reserved in the background once half of this one is used.
+const ID_RESERVATION_BLOCK: u64 = 1_000;
+
+#[derive(Debug, Clone)]
+pub enum EventKind {
+    PoolCreation(Arc<PoolCreationEvent>),
+    // The same pool, keyed by transaction_signature, once token analyses landed
+    // and again whenever a later on-chain check (LP burn or lock) changes it
+    PoolEnriched(Arc<PoolCreationEvent>),
+    BondingCurveCreated(Arc<BondingCurveEvent>),
+    BondingCurveMigrated(Arc<MigrationEvent>),
+    Raw { subscription: String, data: Value },
+    ParseError { subscription: String, error: InstructionError },
+    Status(SubscriptionStatus),
+}
+
+#[derive(Debug, Clone)]
+pub struct StreamEvent {
+    // Assigned by the bus on publish; monotonic for the lifetime of the event store
+    pub id: u64,
+    pub timestamp: String,
+    pub kind: EventKind,
+}
+
+impl StreamEvent {
+    pub fn new(kind: EventKind) -> Self {
+        Self {
+            id: 0,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            kind,
+        }
+    }
+
+    // An event loaded back from the event store, keeping its original id
+    pub fn replayed(id: u64, timestamp: String, kind: EventKind) -> Self {
+        Self { id, timestamp, kind }
+    }
+
+    // Used both as the SSE event name and as event_type in the JSON
+    pub fn event_type(&self) -> &'static str {
+        match &self.kind {
+            EventKind::PoolCreation(_) => "pool_creation",
+            EventKind::PoolEnriched(_) => "pool_enriched",
+            EventKind::BondingCurveCreated(_) => "bonding_curve_created",
+            EventKind::BondingCurveMigrated(_) => "bonding_curve_migrated",
+            EventKind::Raw { .. } => "raw",
+            EventKind::ParseError { .. } => "error",
+            EventKind::Status(_) => "status",
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match &self.kind {
+            EventKind::PoolCreation(pool_event) | EventKind::PoolEnriched(pool_event) => json!({
+                "event_type": self.event_type(),
+                "subscription": pool_event.dex_name,
+                "timestamp": self.timestamp,
+                "data": pool_event.as_ref()
+            }),
+            EventKind::BondingCurveCreated(launch) => json!({
+                "event_type": self.event_type(),
+                "subscription": launch.launchpad,
+                "timestamp": self.timestamp,
+                "data": launch.as_ref()
+            }),
+            EventKind::BondingCurveMigrated(migration) => json!({
+                "event_type": self.event_type(),
+                "subscription": migration.launchpad,
+                "timestamp": self.timestamp,
+                "data": migration.as_ref()
+            }),
+            EventKind::Raw { subscription, data } => json!({
+                "event_type": self.event_type(),
+                "subscription": subscription,
+                "timestamp": self.timestamp,
+                "data": data
+            }),
+            EventKind::ParseError { subscription, error } => json!({
+                "event_type": self.event_type(),
+                "subscription": subscription,
+                "timestamp": self.timestamp,
+                "data": error
+            }),
+            EventKind::Status(status) => json!({
+                "event_type": self.event_type(),
+                "subscription": status.subscription,
+                "timestamp": self.timestamp,
+                "data": status
+            }),
+        }
+    }
+
+    pub fn to_sse(&self) -> String {
+        format!(
+            "event: {}\nid: {}\ndata: {}\n\n",
+            self.event_type(),
+            self.id,
+            self.to_json()
+        )
+    }
+}
+
+// Single fan-out point between the ingestion pipeline and every /stream client.
+// Publishing never blocks: a client that falls more than BUS_CAPACITY events
+// behind skips ahead instead of stalling ingestion.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<StreamEvent>,
+    history: Arc<Mutex<History>>,
+}
+
+struct History {
+    last_id: u64,
+    // Highest id the store has confirmed as reserved
+    reserved_until: u64,
+    // A reservation write is in flight
+    reserving: bool,
+    store: Option<EventStore>,
+    recent: VecDeque<StreamEvent>,
+}
+
+// What a (re)connecting client needs: buffered events it missed, a receiver for
+// everything after them, and where the buffer falls short
+pub struct BusSubscription {
+    pub replay: Vec<StreamEvent>,
+    pub receiver: broadcast::Receiver<StreamEvent>,
+    // Set when events after Last-Event-ID have already left the buffer: the
+    // missed ids are those below this one
+    pub gap_until: Option<u64>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::starting_after(0)
+    }
+
+    // Continues numbering after `last_id`, without persisting anything
+    pub fn starting_after(last_id: u64) -> Self {
+        Self::build(last_id, u64::MAX, None)
+    }
+
+    // Continues numbering after the store's last reserved id and keeps
+    // reserving ids there, so ids are never reissued after a restart
+    pub async fn resume(store: EventStore) -> Result<Self> {
+        let last_id = store.last_event_id().await?;
+        let reserved_until = last_id + ID_RESERVATION_BLOCK;
+        store.reserve_event_ids(reserved_until).await?;
+        Ok(Self::build(last_id, reserved_until, Some(store)))
+    }
+
+    fn build(last_id: u64, reserved_until: u64, store: Option<EventStore>) -> Self {
+        let (sender, _) = broadcast::channel(BUS_CAPACITY);
+        Self {
+            sender,
+            history: Arc::new(Mutex::new(History {
+                last_id,
+                reserved_until,
+                reserving: false,
+                store,
+                recent: VecDeque::with_capacity(REPLAY_CAPACITY),
+            })),
+        }
+    }
+
+    // Returns the id assigned to the event
+    pub fn publish(&self, mut event: StreamEvent) -> u64 {
+        // Held across the send so subscribe_from never sees an event twice or not at all
+        let mut history = self.history.lock().unwrap();
+        history.last_id += 1;
+        event.id = history.last_id;
+
+        if let Some(store) = history.store.clone() {
+            if history.last_id == history.reserved_until + 1 {
+                eprintln!(
+                    "⚠️  Stream ids past {} are not reserved yet and may be reissued after a restart",
+                    history.reserved_until
+                );
+            }
+            if !history.reserving
+                && history.reserved_until.saturating_sub(history.last_id) < ID_RESERVATION_BLOCK / 2
+            {
+                history.reserving = true;
+                let up_to = history.reserved_until.max(history.last_id) + ID_RESERVATION_BLOCK;
+                self.reserve(store, up_to);
+            }
+        }
+
+        if history.recent.len() >= REPLAY_CAPACITY {
+            history.recent.pop_front();
+        }
+        history.recent.push_back(event.clone());
+
+        // An error only means nobody is listening right now
+        let _ = self.sender.send(event);
+        history.last_id
+    }
+
+    // Writes the reservation off the publish path; reserved_until only moves
+    // once the store has it
+    fn reserve(&self, store: EventStore, up_to: u64) {
+        let history = self.history.clone();
+        tokio::spawn(async move {
+            let result = store.reserve_event_ids(up_to).await;
+            let mut history = history.lock().unwrap();
+            history.reserving = false;
+            match result {
+                Ok(()) => history.reserved_until = history.reserved_until.max(up_to),
+                Err(e) => eprintln!("❌ Failed to reserve stream ids up to {}: {:#}", up_to, e),
+            }
+        });
+    }
+
+    // Subscribes and collects the buffered events published after `last_event_id`
+    pub fn subscribe_from(&self, last_event_id: Option<u64>) -> BusSubscription {
+        let history = self.history.lock().unwrap();
+        let receiver = self.sender.subscribe();
+
+        let Some(last_event_id) = last_event_id else {
+            return BusSubscription {
+                replay: Vec::new(),
+                receiver,
+                gap_until: None,
+            };
+        };
+
+        let replay: Vec<StreamEvent> = history
+            .recent
+            .iter()
+            .filter(|event| event.id > last_event_id)
+            .cloned()
+            .collect();
+
+        let first_available = history
+            .recent
+            .front()
+            .map(|event| event.id)
+            .unwrap_or(history.last_id + 1);
+        let gap_until = (last_event_id + 1 < first_available).then_some(first_available);
+
+        BusSubscription {
+            replay,
+            receiver,
+            gap_until,
+        }
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw_event() -> StreamEvent {
+        StreamEvent::new(EventKind::Raw {
+            subscription: "test".to_string(),
+            data: Value::Null,
+        })
+    }
+
+    // Publishes enough events that the oldest ones fall out of the replay buffer
+    fn overflowed_bus(extra: u64) -> EventBus {
+        let bus = EventBus::new();
+        for _ in 0..REPLAY_CAPACITY as u64 + extra {
+            bus.publish(raw_event());
+        }
+        bus
+    }
+
+    fn replay_ids(subscription: &BusSubscription) -> Vec<u64> {
+        subscription.replay.iter().map(|event| event.id).collect()
+    }
+
+    #[test]
+    fn no_last_event_id_replays_nothing() {
+        let bus = overflowed_bus(10);
+        let subscription = bus.subscribe_from(None);
+        assert!(subscription.replay.is_empty());
+        assert_eq!(subscription.gap_until, None);
+    }
+
+    #[test]
+    fn last_event_id_below_buffer_reports_gap() {
+        // Ids 1..=1010 were published; 11..=1010 are still buffered
+        let bus = overflowed_bus(10);
+        let subscription = bus.subscribe_from(Some(5));
+        assert_eq!(subscription.gap_until, Some(11));
+        assert_eq!(subscription.replay.len(), REPLAY_CAPACITY);
+        assert_eq!(subscription.replay.first().map(|event| event.id), Some(11));
+        assert_eq!(subscription.replay.last().map(|event| event.id), Some(1010));
+    }
+
+    #[test]
+    fn last_event_id_just_before_buffer_has_no_gap() {
+        let bus = overflowed_bus(10);
+        let subscription = bus.subscribe_from(Some(10));
+        assert_eq!(subscription.gap_until, None);
+        assert_eq!(subscription.replay.len(), REPLAY_CAPACITY);
+    }
+
+    #[test]
+    fn last_event_id_inside_buffer_replays_the_rest() {
+        let bus = overflowed_bus(10);
+        let subscription = bus.subscribe_from(Some(1005));
+        assert_eq!(subscription.gap_until, None);
+        assert_eq!(replay_ids(&subscription), vec![1006, 1007, 1008, 1009, 1010]);
+    }
+
+    #[test]
+    fn last_event_id_at_or_above_head_replays_nothing() {
+        let bus = overflowed_bus(10);
+        for last_event_id in [1010, 5000] {
+            let subscription = bus.subscribe_from(Some(last_event_id));
+            assert!(subscription.replay.is_empty());
+            assert_eq!(subscription.gap_until, None);
+        }
+    }
+
+    #[test]
+    fn empty_buffer_after_restart_reports_gap_up_to_next_id() {
+        // Nothing published since restarting after id 1000
+        let bus = EventBus::starting_after(1000);
+        assert_eq!(bus.subscribe_from(Some(1000)).gap_until, None);
+
+        let subscription = bus.subscribe_from(Some(990));
+        assert!(subscription.replay.is_empty());
+        assert_eq!(subscription.gap_until, Some(1001));
+    }
+
+    #[test]
+    fn live_events_follow_the_replay() {
+        let bus = EventBus::new();
+        bus.publish(raw_event());
+        let mut subscription = bus.subscribe_from(Some(0));
+        assert_eq!(replay_ids(&subscription), vec![1]);
+
+        bus.publish(raw_event());
+        assert_eq!(subscription.receiver.try_recv().map(|event| event.id).ok(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn resumed_bus_skips_ids_reserved_before_restart() {
+        let path = std::env::temp_dir().join(format!("bus-reserve-{}.db", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+
+        // A first run hands out one id, holding a whole block in the store
+        let store = EventStore::open(&path).unwrap();
+        let bus = EventBus::resume(store.clone()).await.unwrap();
+        assert_eq!(bus.publish(raw_event()), 1);
+
+        // After a restart numbering continues past that block
+        let bus = EventBus::resume(store).await.unwrap();
+        assert_eq!(bus.publish(raw_event()), ID_RESERVATION_BLOCK + 1);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
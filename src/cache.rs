@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::solsniffer::TokenData;
+
+const DEFAULT_TTL_SECS: i64 = 3_600;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedAnalysis {
+    token: TokenData,
+    analyzed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub ttl_secs: i64,
+    pub persisted: bool,
+}
+
+// Token analyses keyed by mint address. Entries expire after the TTL; with a
+// persistence path the cache is written to disk on every change and reloaded
+// at startup so a restart doesn't re-spend API quota.
+#[derive(Clone)]
+pub struct AnalysisCache {
+    inner: Arc<CacheInner>,
+}
+
+struct CacheInner {
+    entries: RwLock<HashMap<String, CachedAnalysis>>,
+    ttl: Duration,
+    path: Option<PathBuf>,
+    // Serialises writes of the cache file
+    write_lock: Mutex<()>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl AnalysisCache {
+    // TOKEN_CACHE_TTL_SECS sets the TTL (default one hour); TOKEN_CACHE_PATH
+    // enables persistence
+    pub fn from_env() -> Result<Self> {
+        let ttl_secs = match std::env::var("TOKEN_CACHE_TTL_SECS") {
+            Ok(value) => value
+                .parse::<i64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .with_context(|| format!("Invalid TOKEN_CACHE_TTL_SECS '{}'", value))?,
+            Err(_) => DEFAULT_TTL_SECS,
+        };
+        let path = std::env::var("TOKEN_CACHE_PATH").ok().map(PathBuf::from);
+
+        Self::new(Duration::seconds(ttl_secs), path)
+    }
+
+    pub fn new(ttl: Duration, path: Option<PathBuf>) -> Result<Self> {
+        let entries = match &path {
+            Some(path) => load_entries(path, ttl)?,
+            None => HashMap::new(),
+        };
+
+        println!(
+            "🗃️  Token analysis cache: TTL {}s, {} entries loaded{}",
+            ttl.num_seconds(),
+            entries.len(),
+            path.as_ref()
+                .map(|path| format!(" from {}", path.display()))
+                .unwrap_or_default()
+        );
+
+        Ok(Self {
+            inner: Arc::new(CacheInner {
+                entries: RwLock::new(entries),
+                ttl,
+                path,
+                write_lock: Mutex::new(()),
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            }),
+        })
+    }
+
+    pub fn get(&self, mint: &str) -> Option<TokenData> {
+        let cached = self
+            .inner
+            .entries
+            .read()
+            .unwrap()
+            .get(mint)
+            .filter(|cached| !self.is_expired(cached))
+            .map(|cached| cached.token.clone());
+
+        match cached {
+            Some(_) => self.inner.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.inner.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        cached
+    }
+
+    pub async fn insert(&self, mint: &str, token: TokenData) {
+        {
+            let mut entries = self.inner.entries.write().unwrap();
+            // Expired entries are only dropped here, so the map can't grow without bound
+            entries.retain(|_, cached| !self.is_expired(cached));
+            entries.insert(
+                mint.to_string(),
+                CachedAnalysis {
+                    token,
+                    analyzed_at: Utc::now(),
+                },
+            );
+        }
+        self.persist().await;
+    }
+
+    // Returns whether the mint was cached
+    pub async fn invalidate(&self, mint: &str) -> bool {
+        let removed = self.inner.entries.write().unwrap().remove(mint).is_some();
+        if removed {
+            println!("🧹 Invalidated cached analysis for {}", mint);
+            self.persist().await;
+        }
+        removed
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.inner.entries.read().unwrap().len(),
+            hits: self.inner.hits.load(Ordering::Relaxed),
+            misses: self.inner.misses.load(Ordering::Relaxed),
+            ttl_secs: self.inner.ttl.num_seconds(),
+            persisted: self.inner.path.is_some(),
+        }
+    }
+
+    fn is_expired(&self, cached: &CachedAnalysis) -> bool {
+        Utc::now() - cached.analyzed_at >= self.inner.ttl
+    }
+
+    async fn persist(&self) {
+        if self.inner.path.is_none() {
+            return;
+        }
+
+        let inner = self.inner.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let _guard = inner.write_lock.lock().unwrap();
+            let path = inner.path.as_ref().expect("checked above");
+            let json = serde_json::to_vec(&*inner.entries.read().unwrap())?;
+
+            // Write then rename so a crash never leaves a truncated cache file
+            let tmp_path = path.with_extension("tmp");
+            std::fs::write(&tmp_path, json)?;
+            std::fs::rename(&tmp_path, path)?;
+            Ok::<_, anyhow::Error>(())
+        })
+        .await;
+
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => eprintln!("❌ Failed to persist token analysis cache: {}", e),
+            Err(e) => eprintln!("❌ Token analysis cache writer panicked: {}", e),
+        }
+    }
+}
+
+fn load_entries(path: &PathBuf, ttl: Duration) -> Result<HashMap<String, CachedAnalysis>> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let contents = std::fs::read(path)
+        .with_context(|| format!("Failed to read token cache '{}'", path.display()))?;
+    let mut entries: HashMap<String, CachedAnalysis> = match serde_json::from_slice(&contents) {
+        Ok(entries) => entries,
+        Err(e) => {
+            // A cache is disposable; don't refuse to start over a bad file
+            eprintln!(
+                "⚠️  Ignoring unreadable token cache '{}': {}",
+                path.display(),
+                e
+            );
+            return Ok(HashMap::new());
+        }
+    };
+
+    let now = Utc::now();
+    entries.retain(|_, cached| now - cached.analyzed_at < ttl);
+    Ok(entries)
+}
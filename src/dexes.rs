@@ -0,0 +1,323 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+
+use crate::liquidity::LiquidityConfig;
+
+const DEFAULT_DEX_CONFIG_PATH: &str = "config/dexes.toml";
+
+// Account names (as reported in Program.AccountNames) carrying the pool's mints and address
+#[derive(Debug, Clone, Deserialize)]
+pub struct DexAccounts {
+    pub token_a_mint: String,
+    pub token_b_mint: String,
+    pub pool: String,
+    // Concentrated-liquidity fee tier account (Orca)
+    #[serde(default)]
+    pub fee_tier: Option<String>,
+    // Accounts holding or owning the pool's reserves; they (and the LP mint)
+    // are left out of holder concentration
+    #[serde(default)]
+    pub vaults: Vec<String>,
+    // LP mint and the creator's account the initial LP tokens are minted to
+    #[serde(default)]
+    pub lp_mint: Option<String>,
+    #[serde(default)]
+    pub creator_lp_account: Option<String>,
+}
+
+// Instruction arguments carrying the initial deposit of each side, or the
+// initial price for pools created without a deposit
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DexArguments {
+    pub token_a_amount: Option<String>,
+    pub token_b_amount: Option<String>,
+    // Q64.64 square root of the token B / token A price
+    pub sqrt_price_x64: Option<String>,
+    pub tick_spacing: Option<String>,
+    // Liquidity-book pools: price = (1 + bin_step / 10000) ^ active_id
+    pub bin_step: Option<String>,
+    pub active_id: Option<String>,
+}
+
+// Replaces the DEX-level account and/or argument mapping for one method
+#[derive(Debug, Clone, Deserialize)]
+pub struct MethodOverride {
+    pub accounts: Option<DexAccounts>,
+    pub arguments: Option<DexArguments>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DexDefinition {
+    pub id: String,
+    pub name: String,
+    // Distinguishes pool flavours of one DEX, e.g. Raydium AmmV4 / Cpmm / Clmm
+    #[serde(default)]
+    pub pool_type: Option<String>,
+    pub program_id: String,
+    pub methods: Vec<String>,
+    pub accounts: DexAccounts,
+    #[serde(default)]
+    pub arguments: DexArguments,
+    // Fee rate in basis points keyed by tick spacing
+    #[serde(default)]
+    pub fee_rates: HashMap<String, f64>,
+    // Per-method mappings for methods whose accounts or arguments differ
+    #[serde(default)]
+    pub method_overrides: HashMap<String, MethodOverride>,
+    // Program-wide authorities that own every pool's vaults
+    #[serde(default)]
+    pub known_vault_owners: Vec<String>,
+}
+
+impl DexDefinition {
+    pub fn handles_method(&self, method_name: &str) -> bool {
+        self.methods.iter().any(|method| method == method_name)
+    }
+
+    // Account and argument mapping that applies to `method_name`
+    pub fn mapping_for(&self, method_name: &str) -> (&DexAccounts, &DexArguments) {
+        let method_override = self.method_overrides.get(method_name);
+        let accounts = method_override
+            .and_then(|o| o.accounts.as_ref())
+            .unwrap_or(&self.accounts);
+        let arguments = method_override
+            .and_then(|o| o.arguments.as_ref())
+            .unwrap_or(&self.arguments);
+        (accounts, arguments)
+    }
+
+    pub fn fee_rate_bps(&self, tick_spacing: u16) -> Option<f64> {
+        self.fee_rates.get(&tick_spacing.to_string()).copied()
+    }
+}
+
+// Account names on a launchpad's create / migrate instructions
+#[derive(Debug, Clone, Deserialize)]
+pub struct LaunchpadAccounts {
+    pub mint: String,
+    pub bonding_curve: String,
+    pub creator: Option<String>,
+    // Pool the curve's liquidity is moved into on migration
+    pub pool: Option<String>,
+}
+
+// Argument names carrying the token metadata on the create instruction
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LaunchpadArguments {
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub uri: Option<String>,
+    pub creator: Option<String>,
+}
+
+// A bonding-curve launchpad such as Pump.fun: tokens are born on the curve and
+// later migrate into a regular DEX pool
+#[derive(Debug, Clone, Deserialize)]
+pub struct LaunchpadDefinition {
+    pub id: String,
+    pub name: String,
+    pub program_id: String,
+    pub create_methods: Vec<String>,
+    pub migrate_methods: Vec<String>,
+    pub accounts: LaunchpadAccounts,
+    #[serde(default)]
+    pub arguments: LaunchpadArguments,
+}
+
+// A program that locks LP tokens; LP held by it (or by an escrow it owns) counts as locked
+#[derive(Debug, Clone, Deserialize)]
+pub struct LpLocker {
+    pub name: String,
+    pub program_id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchAction {
+    Create,
+    Migrate,
+}
+
+// Every DEX and launchpad we watch, loaded once at startup
+#[derive(Debug, Clone, Deserialize)]
+pub struct DexRegistry {
+    #[serde(rename = "dex")]
+    pub dexes: Vec<DexDefinition>,
+    #[serde(rename = "launchpad", default)]
+    pub launchpads: Vec<LaunchpadDefinition>,
+    #[serde(rename = "lp_locker", default)]
+    pub lp_lockers: Vec<LpLocker>,
+    // Quote tokens and the tiers pools are classified into
+    #[serde(default)]
+    pub liquidity: LiquidityConfig,
+}
+
+impl DexRegistry {
+    // DEX_CONFIG_PATH overrides the default config/dexes.toml
+    pub fn from_env() -> Result<Self> {
+        let path = std::env::var("DEX_CONFIG_PATH")
+            .unwrap_or_else(|_| DEFAULT_DEX_CONFIG_PATH.to_string());
+        Self::load(&path)
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read DEX config '{}': {}", path, e))?;
+        let registry: DexRegistry = toml::from_str(&contents)
+            .map_err(|e| anyhow!("Invalid DEX config '{}': {}", path, e))?;
+        registry.validate()?;
+
+        println!(
+            "📚 Loaded {} DEX and {} launchpad definitions from {}: {}",
+            registry.dexes.len(),
+            registry.launchpads.len(),
+            path,
+            registry
+                .dexes
+                .iter()
+                .map(|dex| dex.id.as_str())
+                .chain(registry.launchpads.iter().map(|launchpad| launchpad.id.as_str()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let liquidity = &registry.liquidity;
+        println!(
+            "💧 Quote tokens: {}; liquidity tiers: {}",
+            liquidity
+                .quotes
+                .iter()
+                .map(|quote| quote.symbol.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            liquidity
+                .tiers
+                .iter()
+                .map(|tier| tier.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        Ok(registry)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.dexes.is_empty() {
+            bail!("DEX config defines no DEXes");
+        }
+
+        // Ids and names are matched case-insensitively by the dex= filter, and
+        // an id equal to another DEX's name would be shadowed by that name
+        let names: Vec<&str> = self
+            .dexes
+            .iter()
+            .map(|dex| dex.name.as_str())
+            .chain(self.launchpads.iter().map(|launchpad| launchpad.name.as_str()))
+            .collect();
+        let clashing_name = |id: &str, own_name: &str| {
+            names
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(id) && *name != own_name)
+        };
+
+        let mut ids = HashSet::new();
+        for launchpad in &self.launchpads {
+            if !ids.insert(launchpad.id.to_lowercase()) {
+                bail!("Duplicate launchpad id '{}'", launchpad.id);
+            }
+            if clashing_name(&launchpad.id, &launchpad.name) {
+                bail!("Launchpad id '{}' clashes with another DEX name", launchpad.id);
+            }
+        }
+        for dex in &self.dexes {
+            if !ids.insert(dex.id.to_lowercase()) {
+                bail!("Duplicate DEX id '{}'", dex.id);
+            }
+            if clashing_name(&dex.id, &dex.name) {
+                bail!("DEX id '{}' clashes with another DEX name", dex.id);
+            }
+            if dex.methods.is_empty() {
+                bail!("DEX '{}' lists no methods", dex.id);
+            }
+            for method in dex.method_overrides.keys() {
+                if !dex.handles_method(method) {
+                    bail!("DEX '{}' overrides unknown method '{}'", dex.id, method);
+                }
+            }
+        }
+
+        self.liquidity.validate()
+    }
+
+    // Matches an instruction to its DEX by program address and method.
+    // Without an address only the method name can be used.
+    pub fn find(&self, program_address: Option<&str>, method_name: &str) -> Option<&DexDefinition> {
+        self.dexes.iter().find(|dex| {
+            program_address.is_none_or(|address| address == dex.program_id)
+                && dex.handles_method(method_name)
+        })
+    }
+
+    // Vault owners shared by all pools of every DEX
+    pub fn known_vault_owners(&self) -> HashSet<String> {
+        self.dexes
+            .iter()
+            .flat_map(|dex| dex.known_vault_owners.iter().cloned())
+            .collect()
+    }
+
+    pub fn find_launchpad(
+        &self,
+        program_address: Option<&str>,
+        method_name: &str,
+    ) -> Option<(&LaunchpadDefinition, LaunchAction)> {
+        self.launchpads
+            .iter()
+            .filter(|launchpad| program_address.is_none_or(|address| address == launchpad.program_id))
+            .find_map(|launchpad| {
+                if launchpad.create_methods.iter().any(|m| m == method_name) {
+                    Some((launchpad, LaunchAction::Create))
+                } else if launchpad.migrate_methods.iter().any(|m| m == method_name) {
+                    Some((launchpad, LaunchAction::Migrate))
+                } else {
+                    None
+                }
+            })
+    }
+
+    // Every program to subscribe to with the methods wanted from it, in config
+    // order; DEX entries sharing a program are merged
+    pub fn program_methods(&self) -> Vec<(&str, Vec<&str>)> {
+        let dexes = self
+            .dexes
+            .iter()
+            .map(|dex| (dex.program_id.as_str(), dex.methods.iter().collect::<Vec<_>>()));
+        let launchpads = self.launchpads.iter().map(|launchpad| {
+            (
+                launchpad.program_id.as_str(),
+                launchpad
+                    .create_methods
+                    .iter()
+                    .chain(launchpad.migrate_methods.iter())
+                    .collect(),
+            )
+        });
+
+        let mut programs: Vec<(&str, Vec<&str>)> = Vec::new();
+        for (program_id, methods) in dexes.chain(launchpads) {
+            let index = match programs.iter().position(|(id, _)| *id == program_id) {
+                Some(index) => index,
+                None => {
+                    programs.push((program_id, Vec::new()));
+                    programs.len() - 1
+                }
+            };
+            for method in methods {
+                if !programs[index].1.contains(&method.as_str()) {
+                    programs[index].1.push(method.as_str());
+                }
+            }
+        }
+        programs
+    }
+}
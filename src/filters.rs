@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+use crate::bus::{EventKind, StreamEvent};
+use crate::dexes::DexRegistry;
+use crate::liquidity::{Coin, LiquidityConfig};
+use crate::solsniffer::RiskLevel;
+
+const KNOWN_PARAMS: &[&str] = &[
+    "dex",
+    "min_sol",
+    "max_risk",
+    "exclude_honeypots",
+    "coin_type",
+    "include_raw",
+];
+
+// A requested DEX: a display name covers all its pool types, a config id only its own
+#[derive(Debug, Clone, PartialEq)]
+struct DexMatch {
+    // Lowercased
+    name: String,
+    pool_type: Option<String>,
+}
+
+// Per-client filter for /stream, built from the query string. Pool filters
+// (min_sol, max_risk, exclude_honeypots, coin_type) only apply to pool
+// creations and enrichments; status and error events always pass. Risk
+// filters can't pass a pool whose analysis is still pending, so such clients
+// first hear of it through pool_enriched. min_sol compares the quote side
+// valued in SOL, so stable-quoted pools are filtered on the same scale.
+#[derive(Debug, Clone)]
+pub struct StreamFilter {
+    dexes: Option<Vec<DexMatch>>,
+    min_sol: Option<f64>,
+    max_risk: Option<RiskLevel>,
+    exclude_honeypots: bool,
+    coin_types: Option<Vec<Coin>>,
+    include_raw: bool,
+}
+
+impl StreamFilter {
+    pub fn from_query(params: &HashMap<String, String>, registry: &DexRegistry) -> Result<Self, String> {
+        if let Some(unknown) = params.keys().find(|key| !KNOWN_PARAMS.contains(&key.as_str())) {
+            return Err(format!(
+                "Unknown parameter '{}'; expected one of: {}",
+                unknown,
+                KNOWN_PARAMS.join(", ")
+            ));
+        }
+
+        let dexes = params
+            .get("dex")
+            .map(|value| parse_dexes(value, registry))
+            .transpose()?;
+
+        let min_sol = params
+            .get("min_sol")
+            .map(|value| match value.parse::<f64>() {
+                Ok(min_sol) if min_sol.is_finite() && min_sol >= 0.0 => Ok(min_sol),
+                _ => Err(format!("Invalid min_sol '{}': expected a non-negative number of SOL", value)),
+            })
+            .transpose()?;
+
+        let max_risk = params
+            .get("max_risk")
+            .map(|value| parse_risk_level(value).map_err(|e| format!("Invalid max_risk {}", e)))
+            .transpose()?;
+
+        let coin_types = params
+            .get("coin_type")
+            .map(|value| {
+                split_list(value)
+                    .map(|coin_type| parse_coin_type(coin_type, &registry.liquidity))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+
+        Ok(Self {
+            dexes,
+            min_sol,
+            max_risk,
+            exclude_honeypots: parse_bool(params, "exclude_honeypots", false)?,
+            coin_types,
+            include_raw: parse_bool(params, "include_raw", true)?,
+        })
+    }
+
+    pub fn matches(&self, event: &StreamEvent) -> bool {
+        match &event.kind {
+            EventKind::PoolCreation(pool_event) | EventKind::PoolEnriched(pool_event) => {
+                self.matches_dex(&pool_event.dex_name, pool_event.pool_type.as_deref())
+                    && self.min_sol.is_none_or(|min_sol| {
+                        pool_event.liquidity_sol().is_some_and(|sol| sol >= min_sol)
+                    })
+                    && self
+                        .max_risk
+                        .as_ref()
+                        .is_none_or(|max_risk| pool_event.risk_level() <= *max_risk)
+                    // Until enrichment we can't rule a honeypot out
+                    && !(self.exclude_honeypots
+                        && (pool_event.is_honeypot() || !pool_event.is_enriched()))
+                    && self
+                        .coin_types
+                        .as_ref()
+                        .is_none_or(|coin_types| coin_types.contains(&pool_event.coin_type))
+            }
+            EventKind::BondingCurveCreated(launch) => self.matches_dex(&launch.launchpad, None),
+            EventKind::BondingCurveMigrated(migration) => self.matches_dex(&migration.launchpad, None),
+            EventKind::Raw { .. } => self.include_raw,
+            EventKind::ParseError { .. } | EventKind::Status(_) => true,
+        }
+    }
+
+    fn matches_dex(&self, name: &str, pool_type: Option<&str>) -> bool {
+        let name = name.to_lowercase();
+        self.dexes.as_ref().is_none_or(|dexes| {
+            dexes.iter().any(|dex| {
+                dex.name == name
+                    && dex
+                        .pool_type
+                        .as_deref()
+                        .is_none_or(|wanted| Some(wanted) == pool_type)
+            })
+        })
+    }
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|item| !item.is_empty())
+}
+
+// Accepts display names ("Raydium", every pool type of that DEX) or config
+// ids ("raydium_cpmm", that pool type only), both case-insensitively. Names
+// are tried first; the registry keeps ids from clashing with other names.
+fn parse_dexes(value: &str, registry: &DexRegistry) -> Result<Vec<DexMatch>, String> {
+    let mut matches = Vec::new();
+
+    for requested in split_list(value) {
+        let by_name = registry
+            .dexes
+            .iter()
+            .map(|dex| dex.name.as_str())
+            .chain(registry.launchpads.iter().map(|launchpad| launchpad.name.as_str()))
+            .find(|name| name.eq_ignore_ascii_case(requested))
+            .map(|name| DexMatch {
+                name: name.to_lowercase(),
+                pool_type: None,
+            });
+
+        let by_id = || {
+            registry
+                .dexes
+                .iter()
+                .find(|dex| dex.id.eq_ignore_ascii_case(requested))
+                .map(|dex| DexMatch {
+                    name: dex.name.to_lowercase(),
+                    pool_type: dex.pool_type.clone(),
+                })
+                .or_else(|| {
+                    registry
+                        .launchpads
+                        .iter()
+                        .find(|launchpad| launchpad.id.eq_ignore_ascii_case(requested))
+                        .map(|launchpad| DexMatch {
+                            name: launchpad.name.to_lowercase(),
+                            pool_type: None,
+                        })
+                })
+        };
+
+        let dex_match = by_name.or_else(by_id).ok_or_else(|| {
+            format!(
+                "Unknown dex '{}'; expected one of: {}",
+                requested,
+                registry
+                    .dexes
+                    .iter()
+                    .map(|dex| dex.id.as_str())
+                    .chain(registry.launchpads.iter().map(|launchpad| launchpad.id.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+
+        if !matches.contains(&dex_match) {
+            matches.push(dex_match);
+        }
+    }
+
+    if matches.is_empty() {
+        return Err("Parameter 'dex' is empty".to_string());
+    }
+
+    Ok(matches)
+}
+
+fn parse_bool(params: &HashMap<String, String>, name: &str, default: bool) -> Result<bool, String> {
+    match params.get(name).map(|value| value.to_lowercase()) {
+        None => Ok(default),
+        Some(value) => match value.as_str() {
+            "true" | "1" => Ok(true),
+            "false" | "0" => Ok(false),
+            _ => Err(format!("Invalid {} '{}': expected true or false", name, value)),
+        },
+    }
+}
+
+// A tier from the [liquidity] config, case-insensitive; shared with the
+// coin_type filter of GET /pools
+pub fn parse_coin_type(value: &str, liquidity: &LiquidityConfig) -> Result<Coin, String> {
+    liquidity.tier(value).ok_or_else(|| {
+        format!(
+            "Invalid coin_type '{}': expected one of {}",
+            value,
+            liquidity.tier_names().join(", ")
+        )
+    })
+}
+
+// Case-insensitive, shared with the risk_level filter of GET /pools
+pub fn parse_risk_level(value: &str) -> Result<RiskLevel, String> {
+    match value.to_lowercase().as_str() {
+        "low" => Ok(RiskLevel::Low),
+        "medium" => Ok(RiskLevel::Medium),
+        "high" => Ok(RiskLevel::High),
+        "critical" => Ok(RiskLevel::Critical),
+        "unknown" => Ok(RiskLevel::Unknown),
+        _ => Err(format!("'{}': expected low, medium, high, critical or unknown", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(dex: &str) -> StreamFilter {
+        let registry = DexRegistry::load("config/dexes.toml").expect("shipped DEX config");
+        let params = HashMap::from([("dex".to_string(), dex.to_string())]);
+        StreamFilter::from_query(&params, &registry).expect("valid dex filter")
+    }
+
+    #[test]
+    fn config_id_selects_only_its_pool_type() {
+        let amm = filter("raydium_amm");
+        assert!(amm.matches_dex("Raydium", Some("AmmV4")));
+        assert!(!amm.matches_dex("Raydium", Some("Cpmm")));
+
+        let dynamic = filter("meteora_dynamic");
+        assert!(dynamic.matches_dex("Meteora", Some("DynamicAmm")));
+        assert!(!dynamic.matches_dex("Meteora", Some("Dlmm")));
+    }
+
+    #[test]
+    fn display_name_selects_every_pool_type() {
+        let raydium = filter("Raydium");
+        assert!(raydium.matches_dex("Raydium", Some("AmmV4")));
+        assert!(raydium.matches_dex("Raydium", Some("Clmm")));
+        assert!(!raydium.matches_dex("Meteora", Some("DynamicAmm")));
+
+        assert!(filter("orca whirlpools").matches_dex("Orca Whirlpools", None));
+    }
+
+    #[test]
+    fn letter_case_never_changes_the_match() {
+        for dex in ["raydium", "Raydium", "RAYDIUM"] {
+            let raydium = filter(dex);
+            assert!(raydium.matches_dex("Raydium", Some("AmmV4")), "{}", dex);
+            assert!(raydium.matches_dex("Raydium", Some("Cpmm")), "{}", dex);
+        }
+        for dex in ["meteora_dlmm", "Meteora_DLMM"] {
+            let dlmm = filter(dex);
+            assert!(dlmm.matches_dex("Meteora", Some("Dlmm")), "{}", dex);
+            assert!(!dlmm.matches_dex("Meteora", Some("DynamicAmm")), "{}", dex);
+        }
+    }
+
+    #[test]
+    fn unknown_dex_is_rejected() {
+        let registry = DexRegistry::load("config/dexes.toml").expect("shipped DEX config");
+        let params = HashMap::from([("dex".to_string(), "uniswap".to_string())]);
+        assert!(StreamFilter::from_query(&params, &registry).is_err());
+    }
+}
@@ -0,0 +1,618 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use prettytable::{format, Cell, Row, Table};
+use serde::Serialize;
+
+use crate::bus::{EventBus, EventKind, StreamEvent};
+use crate::dexes::DexRegistry;
+use crate::launches::{BondingCurveEvent, LaunchTracker, MigrationEvent};
+use crate::liquidity::WSOL_MINT;
+use crate::lp::{LpState, LpWatcher, LP_CHECK_DELAYS};
+use crate::parsing::*;
+use crate::price::PriceOracle;
+use crate::risk::TokenAnalyzer;
+use crate::store::EventStore;
+use crate::protocol::{GraphqlWsClient, OperationEvent, WsProtocol};
+use crate::subscriptions::*;
+
+const BITQUERY_WS_URL: &str = "wss://streaming.bitquery.io/eap";
+
+// Reconnect backoff: doubles from the base up to the cap, with jitter
+const RECONNECT_BASE_MS: u64 = 1_000;
+const RECONNECT_MAX_MS: u64 = 60_000;
+// A session that stayed up this long resets the backoff
+const STABLE_SESSION: Duration = Duration::from_secs(30);
+// Connecting, connection_ack and the subscribe message must finish within this
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Subscribed,
+    Disconnected,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionStatus {
+    pub subscription: String,
+    pub state: ConnectionState,
+    pub reconnect_count: u64,
+    pub last_error: Option<String>,
+    pub retry_in_ms: Option<u64>,
+    pub changed_at: String,
+}
+
+// Current connection state of every upstream subscription, shared with the HTTP layer
+#[derive(Clone, Default)]
+pub struct IngestStatus {
+    subscriptions: Arc<RwLock<HashMap<String, SubscriptionStatus>>>,
+    // None when running without a risk provider: tokens carry placeholder risk data
+    risk_provider: Option<String>,
+}
+
+impl IngestStatus {
+    pub fn risk_analysis_enabled(&self) -> bool {
+        self.risk_provider.is_some()
+    }
+
+    pub fn risk_provider(&self) -> Option<&str> {
+        self.risk_provider.as_deref()
+    }
+
+    pub fn snapshot(&self) -> Vec<SubscriptionStatus> {
+        let subscriptions = self.subscriptions.read().unwrap();
+        let mut statuses: Vec<SubscriptionStatus> = subscriptions.values().cloned().collect();
+        statuses.sort_by(|a, b| a.subscription.cmp(&b.subscription));
+        statuses
+    }
+}
+
+// Records state changes for one subscription and announces them on the bus
+struct SubscriptionMonitor {
+    subscription: String,
+    bus: EventBus,
+    status: IngestStatus,
+    launches: LaunchTracker,
+    store: EventStore,
+    analyzer: TokenAnalyzer,
+    // Present when an RPC node is configured
+    lp_watcher: Option<LpWatcher>,
+    registry: Arc<DexRegistry>,
+    // Present unless PRICE_FEED=none
+    price: Option<PriceOracle>,
+}
+
+impl SubscriptionMonitor {
+    fn set_state(&self, state: ConnectionState, last_error: Option<String>, retry_in: Option<Duration>) {
+        let status = {
+            let mut subscriptions = self.status.subscriptions.write().unwrap();
+            let entry = subscriptions
+                .entry(self.subscription.clone())
+                .or_insert_with(|| SubscriptionStatus {
+                    subscription: self.subscription.clone(),
+                    state,
+                    reconnect_count: 0,
+                    last_error: None,
+                    retry_in_ms: None,
+                    changed_at: String::new(),
+                });
+            entry.state = state;
+            entry.retry_in_ms = retry_in.map(|delay| delay.as_millis() as u64);
+            if last_error.is_some() {
+                entry.last_error = last_error;
+            }
+            entry.changed_at = chrono::Utc::now().to_rfc3339();
+            entry.clone()
+        };
+
+        println!("📶 Subscription '{}' is now {:?}", self.subscription, state);
+        self.bus.publish(StreamEvent::new(EventKind::Status(status)));
+    }
+
+    fn record_reconnect(&self) {
+        let mut subscriptions = self.status.subscriptions.write().unwrap();
+        if let Some(entry) = subscriptions.get_mut(&self.subscription) {
+            entry.reconnect_count += 1;
+        }
+    }
+}
+
+#[derive(Default)]
+struct Backoff {
+    attempt: u32,
+}
+
+impl Backoff {
+    fn next_delay(&mut self) -> Duration {
+        let exponential = RECONNECT_BASE_MS
+            .saturating_mul(1 << self.attempt.min(16))
+            .min(RECONNECT_MAX_MS);
+        self.attempt = self.attempt.saturating_add(1);
+
+        // Keep half of the delay and randomise the rest so clients don't reconnect in lockstep
+        let half = exponential / 2;
+        Duration::from_millis(half + rand::random::<u64>() % (half + 1))
+    }
+
+    fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+// Starts the one upstream ingestion pipeline shared by every /stream client.
+// Called once at server boot; parsed events are published onto `bus`.
+pub fn spawn_ingestion(
+    bus: EventBus,
+    registry: Arc<DexRegistry>,
+    store: EventStore,
+    analyzer: TokenAnalyzer,
+    lp_watcher: Option<LpWatcher>,
+    price: Option<PriceOracle>,
+) -> IngestStatus {
+    dotenv::dotenv().ok();
+    let oauth_token = std::env::var("BITQUERY_TOKEN").unwrap_or_else(|_| {
+        eprintln!("❌ BITQUERY_TOKEN not found");
+        "".to_string()
+    });
+
+    println!("🎯 Setting up multiple concurrent subscriptions...");
+
+    // Generated from the DEX config so new DEXes need no code changes
+    let subscriptions = vec![
+        ("combined", combined_subscription(&registry)),
+    ];
+
+    let status = IngestStatus {
+        risk_provider: analyzer.provider_name().map(str::to_string),
+        ..Default::default()
+    };
+    if let Some(price) = &price {
+        price.spawn_refresh();
+    }
+    // Shared so a launch seen on one subscription links pools seen on another
+    let launches = LaunchTracker::default();
+
+    for (sub_name, query) in subscriptions {
+        let monitor = SubscriptionMonitor {
+            subscription: sub_name.to_string(),
+            bus: bus.clone(),
+            status: status.clone(),
+            launches: launches.clone(),
+            store: store.clone(),
+            analyzer: analyzer.clone(),
+            lp_watcher: lp_watcher.clone(),
+            registry: registry.clone(),
+            price: price.clone(),
+        };
+        let oauth_token_clone = oauth_token.clone();
+        let registry = registry.clone();
+
+        tokio::spawn(async move {
+            supervise_subscription(oauth_token_clone, query, registry, monitor).await;
+        });
+    }
+
+    println!("🎧 Listening for real-time data from all subscriptions...\n");
+
+    status
+}
+
+// Keeps one subscription alive for the lifetime of the server, reconnecting
+// with jittered exponential backoff whenever the WebSocket drops.
+async fn supervise_subscription(
+    oauth_token: String,
+    query: String,
+    registry: Arc<DexRegistry>,
+    monitor: SubscriptionMonitor,
+) {
+    let mut backoff = Backoff::default();
+
+    loop {
+        monitor.set_state(ConnectionState::Connecting, None, None);
+        let session_started = Instant::now();
+
+        let error = match handle_subscription(&oauth_token, &query, &registry, &monitor).await {
+            Ok(()) => "Stream closed by server".to_string(),
+            Err(e) => e.to_string(),
+        };
+        println!("❌ Subscription '{}' dropped: {}", monitor.subscription, error);
+
+        if session_started.elapsed() >= STABLE_SESSION {
+            backoff.reset();
+        }
+
+        let delay = backoff.next_delay();
+        monitor.record_reconnect();
+        monitor.set_state(ConnectionState::Disconnected, Some(error), Some(delay));
+        println!("🔁 Reconnecting '{}' in {:?}", monitor.subscription, delay);
+        tokio::time::sleep(delay).await;
+    }
+}
+
+async fn handle_subscription(
+    oauth_token: &str,
+    query: &str,
+    registry: &DexRegistry,
+    monitor: &SubscriptionMonitor,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let subscription_name = &monitor.subscription;
+    let bus = &monitor.bus;
+    let protocol = WsProtocol::from_env();
+    println!("🔌 Connecting to Bitquery WebSocket for '{}' ({:?})...", subscription_name, protocol);
+
+    let operation_id = format!("{}_{}", subscription_name, chrono::Utc::now().timestamp());
+    let handshake = async {
+        let mut client = GraphqlWsClient::connect(BITQUERY_WS_URL, oauth_token, protocol).await?;
+        println!("🤝 Connection acknowledged for '{}'!", subscription_name);
+        monitor.set_state(ConnectionState::Connected, None, None);
+
+        println!("📡 Sending subscription for '{}'...", subscription_name);
+        client.subscribe(&operation_id, query).await?;
+        anyhow::Ok(client)
+    };
+    let mut client = tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake)
+        .await
+        .map_err(|_| format!("Handshake timed out after {:?}", HANDSHAKE_TIMEOUT))??;
+    monitor.set_state(ConnectionState::Subscribed, None, None);
+
+    // Read errors, including an idle socket, end the session so the supervisor can reconnect
+    while let Some(event) = client.next_event().await? {
+        match event {
+            OperationEvent::Next { id, .. } if id != operation_id => {
+                println!("🔍 Ignoring data for unknown operation '{}'", id);
+            }
+            OperationEvent::Next { payload, .. } => {
+                let Some(results) = parse_pool_creation_event(registry, subscription_name, &payload) else {
+                    // For debugging - send raw data with cleaner format
+                    bus.publish(StreamEvent::new(EventKind::Raw {
+                        subscription: subscription_name.clone(),
+                        data: payload,
+                    }));
+                    continue;
+                };
+
+                for result in results {
+                    match result {
+                        Ok(ParsedEvent::PoolCreation(mut pool_event)) => {
+                            monitor.launches.link_pool(&mut pool_event);
+                            publish_pool_event(monitor, *pool_event).await;
+                        }
+                        Ok(ParsedEvent::BondingCurveCreated(launch)) => {
+                            publish_launch_event(bus, &monitor.launches, launch);
+                        }
+                        Ok(ParsedEvent::BondingCurveMigrated(migration)) => {
+                            publish_migration_event(bus, &monitor.launches, migration);
+                        }
+                        Err(error) => {
+                            bus.publish(StreamEvent::new(EventKind::ParseError {
+                                subscription: subscription_name.clone(),
+                                error,
+                            }));
+                        }
+                    }
+                }
+            }
+            OperationEvent::Error { id, payload } => {
+                return Err(format!("Operation '{}' failed: {}", id, payload).into());
+            }
+            OperationEvent::Complete { id } => {
+                println!("🏁 Operation '{}' completed by server", id);
+                if !client.has_active_operations() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+
+
+// Publishes the pool as soon as it is decoded, then enriches it with the token
+// analyses in the background and follows up with a pool_enriched event
+async fn publish_pool_event(monitor: &SubscriptionMonitor, mut pool_event: PoolCreationEvent) {
+    println!("🎯 New pool created on {}: {} - {}",
+        pool_event.dex_name,
+        pool_event.token_a.address,
+        pool_event.token_b.address
+    );
+
+    value_pool(monitor.price.as_ref(), &monitor.registry, &mut pool_event);
+    monitor.analyzer.judge_pool(&mut pool_event);
+    let pool_event = Arc::new(pool_event);
+    let event_id = monitor.bus.publish(StreamEvent::new(EventKind::PoolCreation(pool_event.clone())));
+    save_pool_event(&monitor.store, &pool_event, event_id).await;
+
+    let needs_enrichment = !pool_event.is_enriched();
+    if !needs_enrichment {
+        print_pool_event_table(&pool_event);
+    }
+    let lp_watcher = monitor.lp_watcher.clone().filter(|_| pool_event.lp.is_some());
+    if !needs_enrichment && lp_watcher.is_none() {
+        return;
+    }
+
+    let bus = monitor.bus.clone();
+    let store = monitor.store.clone();
+    let analyzer = monitor.analyzer.clone();
+    let registry = monitor.registry.clone();
+    let price = monitor.price.clone();
+    tokio::spawn(async move {
+        let mut pool_event = pool_event.as_ref().clone();
+
+        if needs_enrichment {
+            pool_event = enrich_pool_event(&analyzer, pool_event).await;
+            // Token decimals and supply are known now
+            value_pool(price.as_ref(), &registry, &mut pool_event);
+            analyzer.judge_pool(&mut pool_event);
+            print_pool_event_table(&pool_event);
+
+            let enriched = Arc::new(pool_event.clone());
+            let event_id = bus.publish(StreamEvent::new(EventKind::PoolEnriched(enriched.clone())));
+            save_pool_event(&store, &enriched, event_id).await;
+        }
+
+        if let Some(lp_watcher) = lp_watcher {
+            watch_lp(&lp_watcher, &analyzer, &bus, &store, pool_event).await;
+        }
+    });
+}
+
+// Uses the last cached price, so publishing never waits on the price feed
+fn value_pool(price: Option<&PriceOracle>, registry: &DexRegistry, pool_event: &mut PoolCreationEvent) {
+    let sol_usd = price.and_then(|price| price.sol_usd());
+    pool_event.revalue(&registry.liquidity, sol_usd);
+}
+
+// Re-checks the pool's LP on a schedule and sends the pool out again as
+// pool_enriched whenever its LP state changes. A burn is final, so watching
+// stops there.
+async fn watch_lp(
+    lp_watcher: &LpWatcher,
+    analyzer: &TokenAnalyzer,
+    bus: &EventBus,
+    store: &EventStore,
+    mut pool_event: PoolCreationEvent,
+) {
+    for delay in LP_CHECK_DELAYS {
+        tokio::time::sleep(delay).await;
+
+        let Some(lp) = &pool_event.lp else {
+            return;
+        };
+        let checked = match lp_watcher.check(lp, &pool_event.transaction_signature).await {
+            Ok(checked) => checked,
+            Err(e) => {
+                eprintln!("⚠️  LP check failed for pool {}: {:#}", pool_event.transaction_signature, e);
+                continue;
+            }
+        };
+
+        let changed = checked.state != lp.state;
+        let burned = checked.state == LpState::Burned;
+        if changed {
+            println!(
+                "🎟️  LP of {} pool {} is now {:?} ({:.1}% burned, {:.1}% locked, {:.1}% with creator)",
+                pool_event.dex_name,
+                pool_event.pool_address.as_deref().unwrap_or("unknown"),
+                checked.state,
+                checked.burned_percentage,
+                checked.locked_percentage,
+                checked.creator_percentage
+            );
+        }
+        pool_event.lp = Some(checked);
+
+        if changed {
+            analyzer.judge_pool(&mut pool_event);
+            let updated = Arc::new(pool_event.clone());
+            let event_id = bus.publish(StreamEvent::new(EventKind::PoolEnriched(updated.clone())));
+            save_pool_event(store, &updated, event_id).await;
+        }
+        if burned {
+            return;
+        }
+    }
+}
+
+// Stored under its latest stream id so clients resuming from an older id can be backfilled
+async fn save_pool_event(store: &EventStore, pool_event: &PoolCreationEvent, event_id: u64) {
+    if let Err(e) = store.save_pool_event(pool_event, event_id).await {
+        eprintln!("❌ Failed to store pool event {}: {}", pool_event.transaction_signature, e);
+    }
+}
+
+fn publish_launch_event(bus: &EventBus, launches: &LaunchTracker, launch: BondingCurveEvent) {
+    launches.record_launch(&launch);
+
+    println!("🌱 New {} token {} ({}) on curve {}, creator {}",
+        launch.launchpad,
+        launch.symbol.as_deref().unwrap_or("?"),
+        launch.mint,
+        launch.bonding_curve,
+        launch.creator.as_deref().unwrap_or("unknown")
+    );
+
+    bus.publish(StreamEvent::new(EventKind::BondingCurveCreated(Arc::new(launch))));
+}
+
+fn publish_migration_event(bus: &EventBus, launches: &LaunchTracker, mut migration: MigrationEvent) {
+    let link = launches.record_migration(&migration);
+    // Only worth attaching when we actually saw the token being born
+    migration.launch = link.launch_signature.is_some().then_some(link);
+
+    println!("🎓 {} token {} migrated from curve {} to pool {}",
+        migration.launchpad,
+        migration.mint,
+        migration.bonding_curve,
+        migration.pool_address.as_deref().unwrap_or("unknown")
+    );
+
+    bus.publish(StreamEvent::new(EventKind::BondingCurveMigrated(Arc::new(migration))));
+}
+
+fn print_pool_event_table(pool_event: &PoolCreationEvent) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+
+    // Header
+    table.add_row(Row::new(vec![
+        Cell::new("🎯 NEW POOL CREATED").style_spec("bFg"),
+    ]));
+
+    // DEX Information
+    table.add_row(Row::new(vec![
+        Cell::new("DEX"),
+        Cell::new(&pool_event.dex_name).style_spec("bFc"),
+    ]));
+
+    if let Some(pool_type) = &pool_event.pool_type {
+        table.add_row(Row::new(vec![
+            Cell::new("Pool Type"),
+            Cell::new(pool_type).style_spec("Fc"),
+        ]));
+    }
+
+    // Pool Address
+    if let Some(pool_addr) = &pool_event.pool_address {
+        table.add_row(Row::new(vec![
+            Cell::new("Pool Address"),
+            Cell::new(&format!("{}...{}", &pool_addr[..8], &pool_addr[pool_addr.len()-8..])).style_spec("Fc"),
+        ]));
+    }
+
+    // Token Information
+    table.add_row(Row::new(vec![
+        Cell::new("Token A"),
+        Cell::new(&format!("{}...{}", &pool_event.token_a.address[..8], &pool_event.token_a.address[pool_event.token_a.address.len()-8..])).style_spec("Fy"),
+    ]));
+
+    table.add_row(Row::new(vec![
+        Cell::new("Token B"),
+        Cell::new(&format!("{}...{}", &pool_event.token_b.address[..8], &pool_event.token_b.address[pool_event.token_b.address.len()-8..])).style_spec("Fy"),
+    ]));
+
+    // Liquidity Information
+    if let Some(token_a_amount) = &pool_event.liquidity_amounts.token_a_amount_formatted {
+        table.add_row(Row::new(vec![
+            Cell::new("Token A Amount"),
+            Cell::new(token_a_amount).style_spec("Fg"),
+        ]));
+    }
+
+    if let Some(token_b_amount) = &pool_event.liquidity_amounts.token_b_amount_formatted {
+        table.add_row(Row::new(vec![
+            Cell::new("Token B Amount"),
+            Cell::new(token_b_amount).style_spec("Fg"),
+        ]));
+    }
+
+    if let Some(sol_amount) = &pool_event.liquidity_amounts.sol_amount_formatted {
+        table.add_row(Row::new(vec![
+            Cell::new("SOL Amount"),
+            Cell::new(&format!("{} SOL", sol_amount)).style_spec("bFg"),
+        ]));
+    }
+
+    if let Some(quote) = &pool_event.liquidity_amounts.quote
+        && quote.mint != WSOL_MINT
+    {
+        table.add_row(Row::new(vec![
+            Cell::new("Quote Amount"),
+            Cell::new(&format!("{} {} (≈{:.2} SOL)", quote.amount_formatted, quote.symbol, quote.value_sol))
+                .style_spec("bFg"),
+        ]));
+    }
+
+    if let Some(valuation) = &pool_event.valuation {
+        let launch_price = match (valuation.token_price_usd, valuation.token_price_sol) {
+            (Some(price_usd), price_sol) => Some(format!("${:.10} ({:.12} SOL)", price_usd, price_sol.unwrap_or_default())),
+            (None, Some(price_sol)) => Some(format!("{:.12} SOL", price_sol)),
+            (None, None) => None,
+        };
+        if let Some(launch_price) = launch_price {
+            table.add_row(Row::new(vec![Cell::new("Launch Price"), Cell::new(&launch_price).style_spec("Fg")]));
+        }
+        if let Some(fdv_usd) = valuation.fdv_usd {
+            table.add_row(Row::new(vec![Cell::new("Initial FDV"), Cell::new(&format!("${:.2}", fdv_usd))]));
+        }
+        if let (Some(liquidity_usd), Some(sol_usd)) = (valuation.liquidity_usd, valuation.sol_usd) {
+            table.add_row(Row::new(vec![
+                Cell::new("Liquidity (USD)"),
+                Cell::new(&format!("${:.2} at ${:.2}/SOL", liquidity_usd, sol_usd)).style_spec("bFg"),
+            ]));
+        }
+    }
+
+    if let Some(initial_price) = pool_event.pool_params.initial_price {
+        table.add_row(Row::new(vec![
+            Cell::new("Initial Price"),
+            Cell::new(&format!("{:.12}", initial_price)).style_spec("Fg"),
+        ]));
+    }
+
+    if let Some(tick_spacing) = pool_event.pool_params.tick_spacing {
+        let fee = pool_event
+            .pool_params
+            .fee_rate_bps
+            .map(|bps| format!(" ({} bps fee)", bps))
+            .unwrap_or_default();
+        table.add_row(Row::new(vec![
+            Cell::new("Tick Spacing"),
+            Cell::new(&format!("{}{}", tick_spacing, fee)),
+        ]));
+    }
+
+    if let Some(bin_step) = pool_event.pool_params.bin_step {
+        table.add_row(Row::new(vec![
+            Cell::new("Bin Step"),
+            Cell::new(&format!(
+                "{} (active bin {})",
+                bin_step,
+                pool_event.pool_params.active_id.unwrap_or_default()
+            )),
+        ]));
+    }
+
+    if let Some(launch) = &pool_event.launch {
+        table.add_row(Row::new(vec![
+            Cell::new("Launched On"),
+            Cell::new(&format!(
+                "{} (curve {})",
+                launch.launchpad, launch.bonding_curve
+            )),
+        ]));
+    }
+
+    if let Some(lp) = &pool_event.lp {
+        table.add_row(Row::new(vec![
+            Cell::new("LP Mint"),
+            Cell::new(&format!("{} ({:?})", lp.lp_mint, lp.state)),
+        ]));
+    }
+
+    if let Some(verdict) = &pool_event.verdict {
+        table.add_row(Row::new(vec![
+            Cell::new("Pool Rules"),
+            Cell::new(&verdict.fired_rules.join(", ")).style_spec("Fr"),
+        ]));
+    }
+
+    // Transaction Information
+    table.add_row(Row::new(vec![
+        Cell::new("Transaction"),
+        Cell::new(&format!("{}...{}", &pool_event.transaction_signature[..8], &pool_event.transaction_signature[pool_event.transaction_signature.len()-8..])).style_spec("Fb"),
+    ]));
+
+    table.add_row(Row::new(vec![
+        Cell::new("Timestamp"),
+        Cell::new(&pool_event.timestamp).style_spec("Fd"),
+    ]));
+
+    println!("\n{}", table);
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+}
@@ -0,0 +1,192 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::dexes::LaunchpadDefinition;
+use crate::parsing::{account_by_name, argument_value, PoolCreationEvent};
+
+// How many recent launches we remember for linking pools back to their curve
+const TRACKED_LAUNCHES: usize = 10_000;
+
+// A token born on a launchpad bonding curve
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BondingCurveEvent {
+    pub launchpad: String,
+    pub mint: String,
+    pub bonding_curve: String,
+    pub creator: Option<String>,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub uri: Option<String>,
+    pub timestamp: String,
+    pub transaction_signature: String,
+}
+
+// A bonding curve completing and moving its liquidity into a DEX pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationEvent {
+    pub launchpad: String,
+    pub mint: String,
+    pub bonding_curve: String,
+    pub pool_address: Option<String>,
+    pub timestamp: String,
+    pub transaction_signature: String,
+    // Filled in when we saw the token being created
+    pub launch: Option<LaunchLink>,
+}
+
+// Where a pool's token came from, attached to the PoolCreationEvent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchLink {
+    pub launchpad: String,
+    pub mint: String,
+    pub bonding_curve: String,
+    pub creator: Option<String>,
+    pub launched_at: Option<String>,
+    pub launch_signature: Option<String>,
+    pub migrated_at: Option<String>,
+    pub migration_signature: Option<String>,
+}
+
+pub fn parse_bonding_curve_create(
+    launchpad: &LaunchpadDefinition,
+    instruction: &Value,
+    timestamp: String,
+    signature: String,
+) -> Result<BondingCurveEvent> {
+    let accounts = instruction
+        .get("Accounts")
+        .and_then(|a| a.as_array())
+        .context("Missing Instruction.Accounts")?;
+    let program = instruction.get("Program").context("Missing Instruction.Program")?;
+
+    let mint = account_by_name(accounts, program, &launchpad.accounts.mint)
+        .with_context(|| format!("Missing {} account", launchpad.accounts.mint))?;
+    let bonding_curve = account_by_name(accounts, program, &launchpad.accounts.bonding_curve)
+        .with_context(|| format!("Missing {} account", launchpad.accounts.bonding_curve))?;
+
+    let arguments = &launchpad.arguments;
+    let argument = |name: &Option<String>| name.as_deref().and_then(|name| argument_value(program, name));
+
+    // Newer instructions pass the creator explicitly; older ones only have the signer account
+    let creator = argument(&arguments.creator).or_else(|| {
+        launchpad
+            .accounts
+            .creator
+            .as_deref()
+            .and_then(|name| account_by_name(accounts, program, name))
+    });
+
+    Ok(BondingCurveEvent {
+        launchpad: launchpad.name.clone(),
+        mint,
+        bonding_curve,
+        creator,
+        name: argument(&arguments.name),
+        symbol: argument(&arguments.symbol),
+        uri: argument(&arguments.uri),
+        timestamp,
+        transaction_signature: signature,
+    })
+}
+
+pub fn parse_bonding_curve_migration(
+    launchpad: &LaunchpadDefinition,
+    instruction: &Value,
+    timestamp: String,
+    signature: String,
+) -> Result<MigrationEvent> {
+    let accounts = instruction
+        .get("Accounts")
+        .and_then(|a| a.as_array())
+        .context("Missing Instruction.Accounts")?;
+    let program = instruction.get("Program").context("Missing Instruction.Program")?;
+
+    let mint = account_by_name(accounts, program, &launchpad.accounts.mint)
+        .with_context(|| format!("Missing {} account", launchpad.accounts.mint))?;
+    let bonding_curve = account_by_name(accounts, program, &launchpad.accounts.bonding_curve)
+        .with_context(|| format!("Missing {} account", launchpad.accounts.bonding_curve))?;
+    let pool_address = launchpad
+        .accounts
+        .pool
+        .as_deref()
+        .and_then(|name| account_by_name(accounts, program, name));
+
+    Ok(MigrationEvent {
+        launchpad: launchpad.name.clone(),
+        mint,
+        bonding_curve,
+        pool_address,
+        timestamp,
+        transaction_signature: signature,
+        launch: None,
+    })
+}
+
+// Remembers recent launches by mint so migrations and the later pool creation
+// can be linked back to the bonding curve the token was born on
+#[derive(Clone, Default)]
+pub struct LaunchTracker {
+    inner: Arc<Mutex<TrackedLaunches>>,
+}
+
+#[derive(Default)]
+struct TrackedLaunches {
+    by_mint: HashMap<String, LaunchLink>,
+    order: VecDeque<String>,
+}
+
+impl TrackedLaunches {
+    fn entry(&mut self, mint: &str, launchpad: &str, bonding_curve: &str) -> &mut LaunchLink {
+        if !self.by_mint.contains_key(mint) {
+            if self.order.len() >= TRACKED_LAUNCHES
+                && let Some(oldest) = self.order.pop_front()
+            {
+                self.by_mint.remove(&oldest);
+            }
+            self.order.push_back(mint.to_string());
+        }
+
+        self.by_mint.entry(mint.to_string()).or_insert_with(|| LaunchLink {
+            launchpad: launchpad.to_string(),
+            mint: mint.to_string(),
+            bonding_curve: bonding_curve.to_string(),
+            creator: None,
+            launched_at: None,
+            launch_signature: None,
+            migrated_at: None,
+            migration_signature: None,
+        })
+    }
+}
+
+impl LaunchTracker {
+    pub fn record_launch(&self, event: &BondingCurveEvent) {
+        let mut launches = self.inner.lock().unwrap();
+        let link = launches.entry(&event.mint, &event.launchpad, &event.bonding_curve);
+        link.creator = event.creator.clone();
+        link.launched_at = Some(event.timestamp.clone());
+        link.launch_signature = Some(event.transaction_signature.clone());
+    }
+
+    // Records the migration and returns the launch it completes
+    pub fn record_migration(&self, event: &MigrationEvent) -> LaunchLink {
+        let mut launches = self.inner.lock().unwrap();
+        let link = launches.entry(&event.mint, &event.launchpad, &event.bonding_curve);
+        link.migrated_at = Some(event.timestamp.clone());
+        link.migration_signature = Some(event.transaction_signature.clone());
+        link.clone()
+    }
+
+    // Attaches the launch of either pool token, if we know it
+    pub fn link_pool(&self, pool_event: &mut PoolCreationEvent) {
+        let launches = self.inner.lock().unwrap();
+        pool_event.launch = [&pool_event.token_a.address, &pool_event.token_b.address]
+            .iter()
+            .find_map(|mint| launches.by_mint.get(mint.as_str()))
+            .cloned();
+    }
+}
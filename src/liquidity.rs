@@ -0,0 +1,341 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::parsing::format_token_amount;
+
+pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+// SOL/USD rate for USD-pegged quotes when neither the config nor a price feed gives one
+const DEFAULT_REFERENCE_SOL_USD: f64 = 150.0;
+
+// Liquidity tier of a pool, named in the [liquidity] config
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Coin(String);
+
+impl fmt::Display for Coin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Denomination {
+    Sol,
+    Usd,
+}
+
+// A token pools are priced against; one whole token is worth one SOL or one USD
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuoteToken {
+    pub symbol: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub mint: String,
+    pub decimals: u8,
+    pub denomination: Denomination,
+}
+
+// Pools whose quote side is worth at least min_sol fall in this tier, or more
+// than min_sol when it is exclusive
+#[derive(Debug, Clone, Deserialize)]
+pub struct LiquidityTier {
+    pub name: String,
+    pub min_sol: f64,
+    #[serde(default)]
+    pub exclusive: bool,
+}
+
+impl LiquidityTier {
+    fn admits(&self, value_sol: f64) -> bool {
+        if self.exclusive {
+            value_sol > self.min_sol
+        } else {
+            value_sol >= self.min_sol
+        }
+    }
+}
+
+impl fmt::Display for LiquidityTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bound = if self.exclusive { ">" } else { "≥" };
+        write!(f, "{} ({}{} SOL)", self.name, bound, self.min_sol)
+    }
+}
+
+// The quote side of a pool's initial deposit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuoteLiquidity {
+    pub symbol: String,
+    pub mint: String,
+    pub amount: String,
+    pub amount_formatted: String,
+    // Common unit every pool is tiered in
+    pub value_sol: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LiquidityConfig {
+    // Converts USD-pegged quotes into SOL while no live price is available
+    #[serde(default = "default_reference_sol_usd")]
+    pub reference_sol_usd: f64,
+    // In order of preference when both sides of a pool are quotes
+    #[serde(rename = "quote")]
+    pub quotes: Vec<QuoteToken>,
+    // Ascending by min_sol
+    #[serde(rename = "tier")]
+    pub tiers: Vec<LiquidityTier>,
+}
+
+fn default_reference_sol_usd() -> f64 {
+    DEFAULT_REFERENCE_SOL_USD
+}
+
+// SOL quotes and the original tiers (exactly 4000 SOL is still GenuineCoin),
+// for DEX configs without a [liquidity] section
+impl Default for LiquidityConfig {
+    fn default() -> Self {
+        let tier = |name: &str, min_sol: f64, exclusive: bool| LiquidityTier {
+            name: name.to_string(),
+            min_sol,
+            exclusive,
+        };
+        Self {
+            reference_sol_usd: DEFAULT_REFERENCE_SOL_USD,
+            quotes: vec![QuoteToken {
+                symbol: "WSOL".to_string(),
+                name: Some("Wrapped SOL".to_string()),
+                mint: WSOL_MINT.to_string(),
+                decimals: 9,
+                denomination: Denomination::Sol,
+            }],
+            tiers: vec![
+                tier("ImmediateCoin", 0.0, false),
+                tier("ShortTermCoin", 120.0, false),
+                tier("GenuineCoin", 200.0, false),
+                tier("MegaCoin", 4000.0, true),
+            ],
+        }
+    }
+}
+
+impl LiquidityConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !self.reference_sol_usd.is_finite() || self.reference_sol_usd <= 0.0 {
+            bail!("liquidity.reference_sol_usd must be a positive number");
+        }
+        if self.quotes.is_empty() {
+            bail!("liquidity config defines no quote tokens");
+        }
+        let mut mints = HashSet::new();
+        for quote in &self.quotes {
+            if !mints.insert(quote.mint.as_str()) {
+                bail!("Quote mint {} listed twice", quote.mint);
+            }
+        }
+
+        match self.tiers.first() {
+            None => bail!("liquidity config defines no tiers"),
+            Some(lowest) if lowest.min_sol != 0.0 || lowest.exclusive => {
+                bail!("Lowest liquidity tier '{}' must start at min_sol = 0, inclusive", lowest.name)
+            }
+            Some(_) => {}
+        }
+        let mut names = HashSet::new();
+        for pair in self.tiers.windows(2) {
+            if pair[1].min_sol <= pair[0].min_sol {
+                bail!("Liquidity tiers must be in ascending min_sol order ('{}')", pair[1].name);
+            }
+        }
+        for tier in &self.tiers {
+            if !names.insert(tier.name.as_str()) {
+                bail!("Duplicate liquidity tier '{}'", tier.name);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn quote(&self, mint: &str) -> Option<&QuoteToken> {
+        self.quotes.iter().find(|quote| quote.mint == mint)
+    }
+
+    pub fn tier(&self, name: &str) -> Option<Coin> {
+        self.tiers
+            .iter()
+            .find(|tier| tier.name.eq_ignore_ascii_case(name))
+            .map(|tier| Coin(tier.name.clone()))
+    }
+
+    pub fn tier_names(&self) -> Vec<&str> {
+        self.tiers.iter().map(|tier| tier.name.as_str()).collect()
+    }
+
+    // Pools without a known quote deposit land in the lowest tier
+    pub fn classify(&self, value_sol: Option<f64>) -> Coin {
+        let value_sol = value_sol.unwrap_or(0.0);
+        let tier = self
+            .tiers
+            .iter()
+            .rev()
+            .find(|tier| tier.admits(value_sol))
+            .unwrap_or(&self.tiers[0]);
+        Coin(tier.name.clone())
+    }
+
+    // The quote token of a pair and whether it is token B
+    pub fn quote_side(&self, token_a: &str, token_b: &str) -> Option<(&QuoteToken, bool)> {
+        self.quotes.iter().find_map(|quote| {
+            if quote.mint == token_a {
+                Some((quote, false))
+            } else if quote.mint == token_b {
+                Some((quote, true))
+            } else {
+                None
+            }
+        })
+    }
+
+    // Value of one whole quote token in SOL; USD quotes use the live rate
+    // when there is one
+    pub fn quote_value_sol(&self, quote: &QuoteToken, sol_usd: Option<f64>) -> f64 {
+        match quote.denomination {
+            Denomination::Sol => 1.0,
+            Denomination::Usd => 1.0 / sol_usd.unwrap_or(self.reference_sol_usd),
+        }
+    }
+
+    // Picks the quote side of a pair and values its raw deposit in SOL
+    pub fn quote_liquidity(
+        &self,
+        token_a: (&str, &Option<String>),
+        token_b: (&str, &Option<String>),
+        sol_usd: Option<f64>,
+    ) -> Option<QuoteLiquidity> {
+        let (quote, quote_is_b) = self.quote_side(token_a.0, token_b.0)?;
+        let amount = if quote_is_b { token_b.1 } else { token_a.1 }.as_ref()?;
+
+        let units = match amount.parse::<u64>() {
+            Ok(raw) => raw as f64 / 10f64.powi(quote.decimals as i32),
+            Err(e) => {
+                eprintln!("Failed to parse {} amount '{}' to u64: {}", quote.symbol, amount, e);
+                return None;
+            }
+        };
+
+        Some(QuoteLiquidity {
+            symbol: quote.symbol.clone(),
+            mint: quote.mint.clone(),
+            amount: amount.clone(),
+            amount_formatted: format_token_amount(amount, quote.decimals),
+            value_sol: units * self.quote_value_sol(quote, sol_usd),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dexes::DexRegistry;
+
+    const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const TOKEN_MINT: &str = "TokenMint111111111111111111111111111111111";
+
+    fn with_tiers(tiers: &[(&str, f64)]) -> LiquidityConfig {
+        LiquidityConfig {
+            tiers: tiers
+                .iter()
+                .map(|(name, min_sol)| LiquidityTier {
+                    name: name.to_string(),
+                    min_sol: *min_sol,
+                    exclusive: false,
+                })
+                .collect(),
+            ..LiquidityConfig::default()
+        }
+    }
+
+    fn with_usdc() -> LiquidityConfig {
+        let mut config = LiquidityConfig::default();
+        config.quotes.push(QuoteToken {
+            symbol: "USDC".to_string(),
+            name: None,
+            mint: USDC_MINT.to_string(),
+            decimals: 6,
+            denomination: Denomination::Usd,
+        });
+        config
+    }
+
+    #[test]
+    fn classify_at_each_tier_boundary() {
+        let config = LiquidityConfig::default();
+        let tier = |value_sol| config.classify(Some(value_sol)).to_string();
+
+        assert_eq!(config.classify(None).to_string(), "ImmediateCoin");
+        assert_eq!(tier(0.0), "ImmediateCoin");
+        assert_eq!(tier(119.99), "ImmediateCoin");
+        assert_eq!(tier(120.0), "ShortTermCoin");
+        assert_eq!(tier(199.99), "ShortTermCoin");
+        assert_eq!(tier(200.0), "GenuineCoin");
+        assert_eq!(tier(4000.0), "GenuineCoin");
+        assert_eq!(tier(4000.01), "MegaCoin");
+    }
+
+    #[test]
+    fn shipped_config_keeps_the_default_tiers() {
+        let registry = DexRegistry::load("config/dexes.toml").expect("shipped DEX config");
+        let shipped = &registry.liquidity;
+        assert_eq!(shipped.classify(Some(4000.0)).to_string(), "GenuineCoin");
+        assert_eq!(shipped.classify(Some(4000.01)).to_string(), "MegaCoin");
+    }
+
+    #[test]
+    fn validate_rejects_unsorted_tiers() {
+        let config = with_tiers(&[("Low", 0.0), ("High", 200.0), ("Mid", 120.0)]);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_duplicate_tier_names() {
+        let config = with_tiers(&[("Low", 0.0), ("High", 120.0), ("High", 200.0)]);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_lowest_tier_above_zero() {
+        let config = with_tiers(&[("Low", 10.0), ("High", 120.0)]);
+        assert!(config.validate().is_err());
+        assert!(with_tiers(&[("Low", 0.0), ("High", 120.0)]).validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_duplicate_quote_mints() {
+        let mut config = LiquidityConfig::default();
+        let wsol = config.quotes[0].clone();
+        config.quotes.push(wsol);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn usdc_quote_uses_the_live_rate_when_there_is_one() {
+        let config = with_usdc();
+        // 30,000 USDC deposited against the token
+        let deposit = Some("30000000000".to_string());
+        let token = (TOKEN_MINT, &None);
+        let usdc = (USDC_MINT, &deposit);
+
+        let live = config.quote_liquidity(token, usdc, Some(200.0)).expect("USDC side");
+        assert_eq!(live.symbol, "USDC");
+        assert!((live.value_sol - 150.0).abs() < 1e-9);
+        assert_eq!(config.classify(Some(live.value_sol)).to_string(), "ShortTermCoin");
+
+        let reference = config.quote_liquidity(token, usdc, None).expect("USDC side");
+        assert!((reference.value_sol - 200.0).abs() < 1e-9);
+        assert_eq!(config.classify(Some(reference.value_sol)).to_string(), "GenuineCoin");
+    }
+}
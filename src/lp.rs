@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::dexes::DexRegistry;
+use crate::onchain::MintInspector;
+
+// Tokens sent here can never be moved again
+const BURN_ADDRESS: &str = "1nc1nerator11111111111111111111111111111111";
+// Share of the LP that has to be burned (or burned plus locked) for the pool to count as such
+const SETTLED_PCT: f64 = 95.0;
+const CREATOR_HELD_PCT: f64 = 50.0;
+
+// When the LP is re-checked after the pool is published. Creators usually burn
+// or lock within minutes, but a lock can also be released later.
+pub const LP_CHECK_DELAYS: [Duration; 5] = [
+    Duration::from_secs(30),
+    Duration::from_secs(2 * 60),
+    Duration::from_secs(10 * 60),
+    Duration::from_secs(30 * 60),
+    Duration::from_secs(60 * 60),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LpState {
+    // Not looked at on chain yet
+    Unchecked,
+    Burned,
+    // In a known locker program, possibly partly burned
+    Locked,
+    HeldByCreator,
+    // Neither burned, locked nor with the creator
+    Unlocked,
+    // Neither burned nor locked by what is visible now, but the LP minted at
+    // creation is not known, so an earlier burn can't be ruled out
+    Unknown,
+}
+
+// What happened to the LP tokens minted when the pool was created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LpStatus {
+    pub lp_mint: String,
+    pub creator_lp_account: Option<String>,
+    pub state: LpState,
+    // Shares of the highest LP supply seen, or of the current supply while
+    // peak_supply is unknown
+    pub burned_percentage: f64,
+    pub locked_percentage: f64,
+    pub creator_percentage: f64,
+    pub lockers: Vec<String>,
+    // Burns show up as the supply dropping below this. Seeded from the LP the
+    // creation transaction minted; None until that could be read.
+    pub peak_supply: Option<u64>,
+    pub checked_at: Option<String>,
+}
+
+impl LpStatus {
+    pub fn new(lp_mint: String, creator_lp_account: Option<String>) -> Self {
+        Self {
+            lp_mint,
+            creator_lp_account,
+            state: LpState::Unchecked,
+            burned_percentage: 0.0,
+            locked_percentage: 0.0,
+            creator_percentage: 0.0,
+            lockers: Vec::new(),
+            peak_supply: None,
+            checked_at: None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct LpWatcher {
+    inspector: Arc<MintInspector>,
+    // Locker program id -> name
+    lockers: Arc<HashMap<String, String>>,
+}
+
+impl LpWatcher {
+    pub fn new(inspector: Arc<MintInspector>, registry: &DexRegistry) -> Self {
+        let lockers = registry
+            .lp_lockers
+            .iter()
+            .map(|locker| (locker.program_id.clone(), locker.name.clone()))
+            .collect();
+
+        Self {
+            inspector,
+            lockers: Arc::new(lockers),
+        }
+    }
+
+    // Fresh status for `lp`; the peak supply carries over so earlier burns still count
+    pub async fn check(&self, lp: &LpStatus, creation_signature: &str) -> Result<LpStatus> {
+        let minted = match lp.peak_supply {
+            Some(peak_supply) => Some(peak_supply),
+            None => self.minted_supply(lp, creation_signature).await,
+        };
+        let supply = self.inspector.token_supply(&lp.lp_mint).await?;
+        let holdings = self.inspector.largest_holdings(&lp.lp_mint).await?;
+        let owner_programs = self
+            .inspector
+            .owner_programs(holdings.iter().map(|holding| holding.owner.as_str()))
+            .await?;
+        let creator_amount = match &lp.creator_lp_account {
+            Some(account) => self.inspector.token_account_amount(account).await?,
+            None => 0,
+        };
+
+        let peak_supply = minted.map(|minted| minted.max(supply));
+        // Without the minted supply only burns to the incinerator are visible
+        let basis = peak_supply.unwrap_or(supply);
+        let share = |amount: u64| {
+            if basis == 0 {
+                0.0
+            } else {
+                amount as f64 / basis as f64 * 100.0
+            }
+        };
+
+        let mut burned = basis - supply;
+        let mut locked = 0;
+        let mut lockers = Vec::new();
+        for holding in &holdings {
+            if holding.owner == BURN_ADDRESS {
+                burned += holding.amount;
+                continue;
+            }
+
+            // Lockers hold LP either directly or in an escrow account they own
+            let locker = self.lockers.get(&holding.owner).or_else(|| {
+                owner_programs
+                    .get(&holding.owner)
+                    .and_then(|program| self.lockers.get(program))
+            });
+            if let Some(locker) = locker {
+                locked += holding.amount;
+                if !lockers.contains(locker) {
+                    lockers.push(locker.clone());
+                }
+            }
+        }
+
+        // Nothing was ever seen in circulation: it was all burned before the first check
+        let (burned_percentage, locked_percentage, creator_percentage) = if basis == 0 {
+            (100.0, 0.0, 0.0)
+        } else {
+            (share(burned), share(locked), share(creator_amount))
+        };
+
+        let state = if burned_percentage >= SETTLED_PCT {
+            LpState::Burned
+        } else if burned_percentage + locked_percentage >= SETTLED_PCT {
+            LpState::Locked
+        } else if peak_supply.is_none() {
+            LpState::Unknown
+        } else if creator_percentage >= CREATOR_HELD_PCT {
+            LpState::HeldByCreator
+        } else {
+            LpState::Unlocked
+        };
+
+        Ok(LpStatus {
+            lp_mint: lp.lp_mint.clone(),
+            creator_lp_account: lp.creator_lp_account.clone(),
+            state,
+            burned_percentage,
+            locked_percentage,
+            creator_percentage,
+            lockers,
+            peak_supply,
+            checked_at: Some(chrono::Utc::now().to_rfc3339()),
+        })
+    }
+
+    // LP minted by the pool's creation transaction; None when it can't be read
+    async fn minted_supply(&self, lp: &LpStatus, creation_signature: &str) -> Option<u64> {
+        match self
+            .inspector
+            .held_after_transaction(creation_signature, &lp.lp_mint)
+            .await
+        {
+            Ok(0) => {
+                eprintln!("⚠️  Creation transaction {} shows no LP of {}", creation_signature, lp.lp_mint);
+                None
+            }
+            Ok(minted) => Some(minted),
+            Err(e) => {
+                eprintln!("⚠️  LP minted at creation unknown for {}: {:#}", lp.lp_mint, e);
+                None
+            }
+        }
+    }
+}
@@ -1,26 +1,62 @@
-use anyhow::{Result};
+use async_tungstenite::tungstenite::http::header;
+use futures::{future, stream, StreamExt};
+use serde_json::json;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream, IntervalStream};
 
-use async_tungstenite::tungstenite::{
-    client::IntoClientRequest,
-    http::{header, HeaderValue},
-    Message,
-};
-use futures::{SinkExt, StreamExt};
-use serde_json::{json, Value};
-use prettytable::{Table, Row, Cell, format};
-
-use actix_web::{get, App, HttpServer, HttpResponse, web::Bytes, Result as ActixResult};
-use tokio::sync::mpsc;
-use tokio_stream::wrappers::ReceiverStream;
+use actix_web::{delete, get, web, App, HttpRequest, HttpServer, HttpResponse, web::Bytes, Result as ActixResult};
 
 use actix_cors::Cors;
 
+mod bus;
+mod cache;
+mod dexes;
+mod filters;
+mod ingest;
+mod launches;
+mod liquidity;
+mod lp;
+mod onchain;
+mod protocol;
+mod rate_limit;
+mod risk;
+mod rugcheck;
+mod rules;
 mod subscriptions;
 mod parsing;
+mod price;
 mod solsniffer;
-
-use subscriptions::*;
-use parsing::*;
+mod store;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bus::{EventBus, EventKind, StreamEvent};
+use dexes::DexRegistry;
+use filters::{parse_coin_type, StreamFilter};
+use cache::AnalysisCache;
+use lp::LpWatcher;
+use onchain::MintInspector;
+use price::PriceOracle;
+use risk::TokenAnalyzer;
+use rules::RuleSet;
+use ingest::IngestStatus;
+use store::{EventStore, PoolQuery};
+
+// Reconnect delay suggested to EventSource clients
+const SSE_RETRY_MS: u64 = 3_000;
+// Comment frames keep idle proxies from closing the stream; a failed write is
+// also how a disconnected client is noticed when no events are flowing
+const SSE_KEEPALIVE: std::time::Duration = std::time::Duration::from_secs(15);
+
+// Lives as long as a client's stream; actix drops the stream once a write to a
+// closed connection fails, which releases the bus receiver along with it
+struct StreamClient;
+
+impl Drop for StreamClient {
+    fn drop(&mut self) {
+        println!("👋 Stream client disconnected");
+    }
+}
 
 
 
@@ -41,18 +77,95 @@ use parsing::*;
 async fn main() -> std::io::Result<()> {
     println!("Server starting on http://127.0.0.1:8080");
     println!("Access the SSE stream at http://127.0.0.1:8080/stream");
+    println!("Upstream connection status at http://127.0.0.1:8080/status");
+    println!("Pool history at http://127.0.0.1:8080/pools");
+
+    dotenv::dotenv().ok();
+    let registry = DexRegistry::from_env().map_err(|e| {
+        eprintln!("❌ {}", e);
+        std::io::Error::other(e.to_string())
+    })?;
+    let rules = RuleSet::from_env().map_err(|e| {
+        eprintln!("❌ {}", e);
+        std::io::Error::other(e.to_string())
+    })?;
+    let store = EventStore::from_env().map_err(|e| {
+        eprintln!("❌ {:#}", e);
+        std::io::Error::other(e.to_string())
+    })?;
+
+    // Without a key every token would silently get placeholder risk data, so
+    // that mode has to be asked for explicitly
+    let allow_missing_key = std::env::var("ALLOW_MISSING_SOLSNIFER_KEY")
+        .is_ok_and(|value| value.eq_ignore_ascii_case("true"));
+    let risk_provider = risk::provider_from_env(allow_missing_key).map_err(|e| {
+        eprintln!("❌ {:#}", e);
+        std::io::Error::other(e.to_string())
+    })?;
+    if risk_provider.is_none() {
+        eprintln!("⚠️  No risk provider available - running in DEGRADED mode with on-chain mint checks only");
+    }
+
+    let cache = AnalysisCache::from_env().map_err(|e| {
+        eprintln!("❌ {:#}", e);
+        std::io::Error::other(e.to_string())
+    })?;
+    let inspector = MintInspector::from_env(registry.known_vault_owners()).map(Arc::new);
+    if inspector.is_none() {
+        eprintln!("⚠️  HTTPS_ENDPOINT not set - mint authorities are taken from risk reports unchecked");
+    }
+    let lp_watcher = inspector
+        .as_ref()
+        .map(|inspector| LpWatcher::new(inspector.clone(), &registry));
+    let price = PriceOracle::from_env().map_err(|e| {
+        eprintln!("❌ {:#}", e);
+        std::io::Error::other(e.to_string())
+    })?;
+    if price.is_none() {
+        eprintln!("⚠️  No SOL/USD price feed - pools are published without USD valuation");
+    }
+    let analyzer = TokenAnalyzer::new(risk_provider, inspector, Arc::new(rules), cache.clone());
+
+    // One upstream pipeline for the whole server; clients only subscribe to the bus
+    let bus = EventBus::resume(store.clone()).await.map_err(|e| {
+        eprintln!("❌ {:#}", e);
+        std::io::Error::other(e.to_string())
+    })?;
+    let registry = Arc::new(registry);
+    let ingest_status = ingest::spawn_ingestion(
+        bus.clone(),
+        registry.clone(),
+        store.clone(),
+        analyzer,
+        lp_watcher,
+        price,
+    );
 
-    HttpServer::new(|| {
+    HttpServer::new(move || {
         // Configure CORS middleware
         let cors = Cors::default()
             .allowed_origin("http://localhost:3000") // Allow your Next.js frontend
-            .allowed_methods(vec!["GET"]) // SSE uses GET requests
-            .allowed_headers(vec![header::CONTENT_TYPE, header::ACCEPT, header::AUTHORIZATION]) // Include headers your client might send
+            .allowed_methods(vec!["GET", "DELETE"]) // SSE uses GET requests; DELETE invalidates cached analyses
+            .allowed_headers(vec![
+                header::CONTENT_TYPE,
+                header::ACCEPT,
+                header::AUTHORIZATION,
+                header::HeaderName::from_static("last-event-id"),
+            ]) // Include headers your client might send
             .max_age(3600); // Cache preflight requests for 1 hour
 
         App::new()
             .wrap(cors) // Apply the CORS middleware to your application
+            .app_data(web::Data::new(bus.clone()))
+            .app_data(web::Data::new(ingest_status.clone()))
+            .app_data(web::Data::new(store.clone()))
+            .app_data(web::Data::from(registry.clone()))
+            .app_data(web::Data::new(cache.clone()))
             .service(subscribe_to_bitquery)
+            .service(ingest_status_report)
+            .service(list_pools)
+            .service(pool_detail)
+            .service(invalidate_token_analysis)
     })
     .bind(("127.0.0.1", 8080))?
     .run()
@@ -63,269 +176,149 @@ async fn main() -> std::io::Result<()> {
 
 
 #[get("/stream")]
-pub async fn subscribe_to_bitquery() -> ActixResult<HttpResponse> {
+pub async fn subscribe_to_bitquery(
+    req: HttpRequest,
+    bus: web::Data<EventBus>,
+    store: web::Data<EventStore>,
+    registry: web::Data<DexRegistry>,
+    params: web::Query<HashMap<String, String>>,
+) -> ActixResult<HttpResponse> {
+    let filter = match StreamFilter::from_query(&params, &registry) {
+        Ok(filter) => filter,
+        Err(message) => return Ok(HttpResponse::BadRequest().json(json!({ "error": message }))),
+    };
+
+    // Sent by EventSource when it reconnects; anything unparsable means a fresh start
+    let last_event_id = req
+        .headers()
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+
+    let subscription = bus.subscribe_from(last_event_id);
+    println!("🎧 Stream client connected ({} active)", bus.client_count());
+
+    // Events older than the in-memory buffer come from the store; only pools
+    // are persisted, so that is all a long gap can recover
+    let mut replay = Vec::new();
+    if let (Some(after), Some(before)) = (last_event_id, subscription.gap_until) {
+        match store.pool_events_between(after, before).await {
+            Ok(events) => replay.extend(events.into_iter().map(|(id, pool_event)| {
+                let timestamp = pool_event.timestamp.clone();
+                let pool_event = Arc::new(pool_event);
+                let kind = if pool_event.is_enriched() {
+                    EventKind::PoolEnriched(pool_event)
+                } else {
+                    EventKind::PoolCreation(pool_event)
+                };
+                StreamEvent::replayed(id, timestamp, kind)
+            })),
+            Err(e) => eprintln!("❌ Failed to load stored events after id {}: {}", after, e),
+        }
+    }
+    replay.extend(subscription.replay);
+    replay.retain(|event| filter.matches(event));
 
-    dotenv::dotenv().ok();
-    let oauth_token = std::env::var("BITQUERY_TOKEN").unwrap_or_else(|_| {
-        std::env::var("BITQUERY_TOKEN").unwrap_or_else(|_| {
-            eprintln!("❌ BITQUERY_TOKEN not found");
-            "".to_string()
-        })
-    });
-
-    println!("🎯 Setting up multiple concurrent subscriptions...");
-
-    let subscriptions = vec![
-        // ("raydium", RAYDIUM_SUBSCRIPTION),
-        // ("pumpswap", PUMPSWAP_SUBSCRIPTION),
-        // ("meteora", METEORA_SUBSCRIPTION),
-        ("combined", COMBINED),
-    ];
-
-    let (tx, rx) = mpsc::channel::<String>(50);
-    
-    for (sub_name, query) in subscriptions {
-
-        let oauth_token_clone = oauth_token.clone();
-        let tx_clone = tx.clone();
-        let sub_name = sub_name.to_string();
-        let query = query.to_string();
-        
-        tokio::spawn(async move {
-            if let Err(e) = handle_subscription(oauth_token_clone, sub_name.clone(), query, tx_clone).await {
-                println!("❌ Subscription '{}' failed: {}", sub_name, e);
-            }
-        });
+    if let Some(last_event_id) = last_event_id {
+        println!("⏪ Replaying {} events after id {}", replay.len(), last_event_id);
     }
 
-    println!("🎧 Listening for real-time data from all subscriptions...\n");
-    
-    let event_stream = ReceiverStream::new(rx)
-        .map(|data| {
-            Ok::<Bytes, actix_web::Error>(data.into())
+    let retry_hint = stream::once(future::ready(Ok::<Bytes, actix_web::Error>(
+        format!("retry: {}\n\n", SSE_RETRY_MS).into(),
+    )));
+    let replayed = stream::iter(replay.into_iter().map(|event| Ok(event.to_sse().into())));
+    let client = StreamClient;
+    let live = BroadcastStream::new(subscription.receiver)
+        .filter_map(move |event| {
+            let _client = &client;
+            future::ready(match event {
+                Ok(event) if filter.matches(&event) => Some(Ok(event.to_sse().into())),
+                Ok(_) => None,
+                Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                    println!("⚠️  Stream client lagging, skipped {} events", skipped);
+                    None
+                }
+            })
         });
 
+    let mut keepalive_interval = tokio::time::interval(SSE_KEEPALIVE);
+    keepalive_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let keepalive = IntervalStream::new(keepalive_interval)
+        .skip(1) // the first tick fires immediately
+        .map(|_| Ok(Bytes::from_static(b": keepalive\n\n")));
+
     Ok(HttpResponse::Ok()
         .insert_header(("Content-Type", "text/event-stream"))
         .insert_header(("Cache-Control", "no-cache"))
         .insert_header(("Connection", "keep-alive"))
-        .streaming(event_stream))
+        .streaming(retry_hint.chain(replayed).chain(stream::select(live, keepalive))))
 }
 
+#[get("/status")]
+pub async fn ingest_status_report(
+    bus: web::Data<EventBus>,
+    ingest_status: web::Data<IngestStatus>,
+    cache: web::Data<AnalysisCache>,
+) -> ActixResult<HttpResponse> {
+    Ok(HttpResponse::Ok().json(json!({
+        "subscriptions": ingest_status.snapshot(),
+        "stream_clients": bus.client_count(),
+        "risk_analysis": if ingest_status.risk_analysis_enabled() { "enabled" } else { "degraded" },
+        "risk_provider": ingest_status.risk_provider(),
+        "token_cache": cache.stats(),
+    })))
+}
 
-async fn handle_subscription(
-    oauth_token: String,
-    subscription_name: String,
-    query: String,
-    tx: mpsc::Sender<String>,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    println!("🔌 Connecting to Bitquery WebSocket for '{}'...", subscription_name);
-    
-    let mut request = "wss://streaming.bitquery.io/eap".into_client_request()?;
-
-    request.headers_mut().insert(
-        header::SEC_WEBSOCKET_PROTOCOL,
-        HeaderValue::from_str("graphql-transport-ws")?,
-    );
-    request.headers_mut().insert(
-        "Authorization", 
-        HeaderValue::from_str(&format!("Bearer {}", oauth_token))?,
-    );
-
-    let (ws_stream, _) = async_tungstenite::tokio::connect_async(request).await?;
-    let (mut write, mut read) = ws_stream.split();
-
-    println!("✅ Connected '{}'! Initializing connection...", subscription_name);
-
-    let init_msg = json!({
-        "type": "connection_init"
-    });
-    write.send(Message::Text(init_msg.to_string())).await?;
-
-    let mut connection_acked = false;
-    while let Some(msg) = read.next().await {
-        let msg = msg?;
-        if let Message::Text(text) = msg {
-            let parsed: serde_json::Value = serde_json::from_str(&text)?;
-            match parsed["type"].as_str() {
-                Some("connection_ack") => {
-                    println!("🤝 Connection acknowledged for '{}'!", subscription_name);
-                    connection_acked = true;
-                    break;
-                }
-                Some("connection_error") => {
-                    return Err(format!("Connection error for '{}': {}", subscription_name, parsed["payload"]).into());
-                }
-                _ => {
-                    println!("📥 Received init message for '{}': {}", subscription_name, text);
-                }
-            }
+#[get("/pools")]
+pub async fn list_pools(
+    store: web::Data<EventStore>,
+    registry: web::Data<DexRegistry>,
+    query: web::Query<PoolQuery>,
+) -> ActixResult<HttpResponse> {
+    let mut query = query.into_inner();
+    // Checked against the configured tiers so a typo is a 400, as on /stream
+    if let Some(coin_type) = &query.coin_type {
+        match parse_coin_type(&coin_type.to_string(), &registry.liquidity) {
+            Ok(tier) => query.coin_type = Some(tier),
+            Err(message) => return Ok(HttpResponse::BadRequest().json(json!({ "error": message }))),
         }
     }
 
-    if !connection_acked {
-        return Err(format!("Failed to get connection acknowledgment for '{}'", subscription_name).into());
-    }
+    let page = store
+        .query_pools(query)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
 
-    let subscription_msg = json!({
-        "id": format!("{}_{}", subscription_name, chrono::Utc::now().timestamp()),
-        "type": "start",
-        "payload": {
-            "query": query
-        }
-    });
-    
-    println!("📡 Sending subscription for '{}'...", subscription_name);
-    write.send(Message::Text(subscription_msg.to_string())).await?;
-
-    while let Some(msg) = read.next().await {
-        if let Ok(msg) = msg {
-            if let Message::Text(text) = msg {
-                let raw_data: Value = serde_json::from_str(&text).unwrap_or(json!({"raw": text}));
-                
-                // Parse and transform the data - now properly await the async function
-                if let Some(mut pool_event) = parse_pool_creation_event(&subscription_name, &raw_data).await {
-
-                    pool_event = format_pool_event_amounts(pool_event).await;
-
-                    print_pool_event_table(&pool_event);
-
-                    let formatted_message = json!({
-                        "event_type": "pool_creation",
-                        "subscription": pool_event.dex_name,
-                        "timestamp": chrono::Utc::now().to_rfc3339(),
-                        "data": pool_event
-                    });
-                    
-                    println!("🎯 New pool created on {}: {} - {}", 
-                        pool_event.dex_name, 
-                        pool_event.token_a.address, 
-                        pool_event.token_b.address
-                    );
-                    
-                    // Send the formatted message
-                    let formatted_json = serde_json::to_string(&formatted_message)?;
-                    if tx.send(format!("data: {}\n\n", formatted_json)).await.is_err() {
-                        println!("Server: Client disconnected, stopping '{}' stream.", subscription_name);
-                        break;
-                    }
-                } else {
-                    // For debugging - send raw data with cleaner format
-                    let tagged_message = json!({
-                        "event_type": "raw",
-                        "subscription": subscription_name,
-                        "timestamp": chrono::Utc::now().to_rfc3339(),
-                        "data": raw_data
-                    });
-                    
-                    let raw_json = serde_json::to_string(&tagged_message)?;
-                    if tx.send(format!("data: {}\n\n", raw_json)).await.is_err() {
-                        println!("Server: Client disconnected, stopping '{}' stream.", subscription_name);
-                        break;
-                    }
-                }
-            }
-        }
-    }
-    
-    Ok(())
+    Ok(HttpResponse::Ok().json(page))
 }
 
-
-
-async fn format_pool_event_amounts(
-    mut pool_event: PoolCreationEvent
-) -> PoolCreationEvent {
-    // Get decimals for both tokens
-    let token_a_decimals = pool_event.token_a.decimals;
-    let token_b_decimals = pool_event.token_b.decimals;
-
-    // Format amounts if they exist
-    if let Some(ref amount) = pool_event.liquidity_amounts.token_a_amount {
-        pool_event.liquidity_amounts.token_a_amount_formatted = 
-            Some(format_token_amount(amount, token_a_decimals));
-    }
-
-    if let Some(ref amount) = pool_event.liquidity_amounts.token_b_amount {
-        pool_event.liquidity_amounts.token_b_amount_formatted = 
-            Some(format_token_amount(amount, token_b_decimals));
-    }
-
-    if let Some(ref amount) = pool_event.liquidity_amounts.sol_amount {
-        pool_event.liquidity_amounts.sol_amount_formatted = 
-            Some(format_token_amount(amount, 9)); 
-    }
-
-    pool_event
+#[get("/pools/{signature}")]
+pub async fn pool_detail(
+    store: web::Data<EventStore>,
+    signature: web::Path<String>,
+) -> ActixResult<HttpResponse> {
+    let pool = store
+        .get_pool(signature.into_inner())
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    Ok(match pool {
+        Some(pool) => HttpResponse::Ok().json(pool),
+        None => HttpResponse::NotFound().json(json!({ "error": "Pool not found" })),
+    })
 }
 
-fn print_pool_event_table(pool_event: &PoolCreationEvent) {
-    let mut table = Table::new();
-    table.set_format(*format::consts::FORMAT_BOX_CHARS);
-    
-    // Header
-    table.add_row(Row::new(vec![
-        Cell::new("🎯 NEW POOL CREATED").style_spec("bFg"),
-    ]));
-    
-    // DEX Information
-    table.add_row(Row::new(vec![
-        Cell::new("DEX"),
-        Cell::new(&pool_event.dex_name).style_spec("bFc"),
-    ]));
-    
-    // Pool Address
-    if let Some(pool_addr) = &pool_event.pool_address {
-        table.add_row(Row::new(vec![
-            Cell::new("Pool Address"),
-            Cell::new(&format!("{}...{}", &pool_addr[..8], &pool_addr[pool_addr.len()-8..])).style_spec("Fc"),
-        ]));
-    }
-    
-    // Token Information
-    table.add_row(Row::new(vec![
-        Cell::new("Token A"),
-        Cell::new(&format!("{}...{}", &pool_event.token_a.address[..8], &pool_event.token_a.address[pool_event.token_a.address.len()-8..])).style_spec("Fy"),
-    ]));
-    
-    table.add_row(Row::new(vec![
-        Cell::new("Token B"),
-        Cell::new(&format!("{}...{}", &pool_event.token_b.address[..8], &pool_event.token_b.address[pool_event.token_b.address.len()-8..])).style_spec("Fy"),
-    ]));
-    
-    // Liquidity Information
-    if let Some(token_a_amount) = &pool_event.liquidity_amounts.token_a_amount_formatted {
-        table.add_row(Row::new(vec![
-            Cell::new("Token A Amount"),
-            Cell::new(&(token_a_amount)).style_spec("Fg"),
-        ]));
-    }
-    
-    if let Some(token_b_amount) = &pool_event.liquidity_amounts.token_b_amount_formatted {
-        table.add_row(Row::new(vec![
-            Cell::new("Token B Amount"),
-            Cell::new(&(token_b_amount)).style_spec("Fg"),
-        ]));
-    }
-    
-    if let Some(sol_amount) = &pool_event.liquidity_amounts.sol_amount_formatted {
-        table.add_row(Row::new(vec![
-            Cell::new("SOL Amount"),
-            Cell::new(&format!("{} SOL", sol_amount)).style_spec("bFg"),
-        ]));
-    }
-    
-    // Transaction Information
-    table.add_row(Row::new(vec![
-        Cell::new("Transaction"),
-        Cell::new(&format!("{}...{}", &pool_event.transaction_signature[..8], &pool_event.transaction_signature[pool_event.transaction_signature.len()-8..])).style_spec("Fb"),
-    ]));
-    
-    table.add_row(Row::new(vec![
-        Cell::new("Timestamp"),
-        Cell::new(&pool_event.timestamp).style_spec("Fd"),
-    ]));
-    
-    println!("\n{}", table);
-    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+// Forces a fresh analysis the next time the mint shows up in a pool
+#[delete("/cache/tokens/{mint}")]
+pub async fn invalidate_token_analysis(
+    cache: web::Data<AnalysisCache>,
+    mint: web::Path<String>,
+) -> ActixResult<HttpResponse> {
+    let mint = mint.into_inner();
+    let invalidated = cache.invalidate(&mint).await;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "mint": mint,
+        "invalidated": invalidated,
+    })))
 }
@@ -0,0 +1,733 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+use spl_token_2022::extension::default_account_state::DefaultAccountState;
+use spl_token_2022::extension::mint_close_authority::MintCloseAuthority;
+use spl_token_2022::extension::non_transferable::NonTransferable;
+use spl_token_2022::extension::permanent_delegate::PermanentDelegate;
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::transfer_hook::TransferHook;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::state::AccountState;
+
+use crate::solsniffer::{AnalysisStatus, RiskLevel, TokenData};
+
+// Relative difference between reported and on-chain supply we put down to rounding
+const SUPPLY_TOLERANCE: f64 = 0.01;
+// A transfer fee from 10% up makes a token expensive to get out of
+const HIGH_TRANSFER_FEE_BPS: u16 = 1_000;
+// Concentration above which a token gets an audit risk
+const TOP_1_ALERT_PCT: f64 = 30.0;
+const TOP_10_ALERT_PCT: f64 = 80.0;
+
+// Token accounts of both programs start with mint (32 bytes), owner (32) and amount (u64)
+const TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+// Size of an SPL Token account; Token-2022 accounts may be longer
+const SPL_TOKEN_ACCOUNT_LEN: u64 = 165;
+
+// Mint state read straight from the chain, with every point on which the
+// risk provider's report disagreed with it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnChainMint {
+    // "spl-token" or "token-2022"
+    pub program: String,
+    pub mint_authority: Option<String>,
+    pub freeze_authority: Option<String>,
+    // Raw units
+    pub supply: u64,
+    pub decimals: u8,
+    #[serde(default)]
+    pub discrepancies: Vec<String>,
+    // Token-2022 extensions that can work against holders
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<TokenExtension>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub holders: Option<HolderConcentration>,
+}
+
+// Who holds the supply, from the largest token accounts with pool vaults,
+// LP accounts and bonding curves left out. Percentages are of total supply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HolderConcentration {
+    pub top_1_percentage: f64,
+    pub top_10_percentage: f64,
+    // Distinct owners with a balance; None when counting was disabled or the
+    // RPC node refused the account scan
+    pub holder_count: Option<u32>,
+    pub largest_holders: Vec<HolderShare>,
+    // Largest accounts skipped as pool or curve accounts
+    pub excluded_accounts: usize,
+}
+
+pub struct TokenHolding {
+    pub account: String,
+    pub owner: String,
+    // Raw units
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HolderShare {
+    pub owner: String,
+    pub percentage: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "extension", rename_all = "snake_case")]
+pub enum TokenExtension {
+    // The higher of the current and the scheduled fee
+    TransferFee {
+        basis_points: u16,
+        maximum_fee: u64,
+        authority: Option<String>,
+    },
+    TransferHook {
+        program_id: Option<String>,
+        authority: Option<String>,
+    },
+    PermanentDelegate {
+        delegate: Option<String>,
+    },
+    NonTransferable,
+    // New token accounts start frozen until the freeze authority thaws them
+    DefaultAccountFrozen,
+    MintCloseAuthority {
+        authority: Option<String>,
+    },
+}
+
+impl TokenExtension {
+    // Lowest risk level a token with this extension can have
+    pub fn severity(&self) -> RiskLevel {
+        match self {
+            TokenExtension::NonTransferable
+            | TokenExtension::DefaultAccountFrozen
+            | TokenExtension::PermanentDelegate { delegate: Some(_) } => RiskLevel::Critical,
+            TokenExtension::TransferFee { basis_points, .. } if *basis_points >= HIGH_TRANSFER_FEE_BPS => {
+                RiskLevel::High
+            }
+            TokenExtension::TransferHook { program_id: Some(_), .. } => RiskLevel::High,
+            // Fees can be raised later while an authority remains
+            TokenExtension::TransferFee { basis_points, authority, .. }
+                if *basis_points > 0 || authority.is_some() =>
+            {
+                RiskLevel::Medium
+            }
+            TokenExtension::MintCloseAuthority { authority: Some(_) } => RiskLevel::Medium,
+            _ => RiskLevel::Low,
+        }
+    }
+
+    // Holders may be unable to sell at all
+    pub fn blocks_selling(&self) -> bool {
+        matches!(
+            self,
+            TokenExtension::NonTransferable | TokenExtension::DefaultAccountFrozen
+        )
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            TokenExtension::TransferFee { basis_points, maximum_fee, authority } => format!(
+                "transfer fee {:.2}% (max {} raw units){}",
+                *basis_points as f64 / 100.0,
+                maximum_fee,
+                if authority.is_some() { ", fee can be changed" } else { "" }
+            ),
+            TokenExtension::TransferHook { program_id, .. } => match program_id {
+                Some(program_id) => format!("transfer hook program {}", program_id),
+                None => "transfer hook (no program set)".to_string(),
+            },
+            TokenExtension::PermanentDelegate { delegate } => match delegate {
+                Some(delegate) => format!("permanent delegate {} can move or burn any holder's tokens", delegate),
+                None => "permanent delegate (none set)".to_string(),
+            },
+            TokenExtension::NonTransferable => "non-transferable".to_string(),
+            TokenExtension::DefaultAccountFrozen => "new token accounts are frozen by default".to_string(),
+            TokenExtension::MintCloseAuthority { authority } => match authority {
+                Some(authority) => format!("mint can be closed by {}", authority),
+                None => "mint close authority (none set)".to_string(),
+            },
+        }
+    }
+}
+
+impl OnChainMint {
+    pub fn supply_amount(&self) -> f64 {
+        self.supply as f64 / 10f64.powi(self.decimals as i32)
+    }
+}
+
+pub struct MintInspector {
+    rpc: RpcClient,
+    // Owners of pool vaults across every pool of a DEX
+    known_vault_owners: HashSet<String>,
+    count_holders: bool,
+}
+
+impl MintInspector {
+    // Uses the RPC node in HTTPS_ENDPOINT; returns None when it is not set.
+    // Holder counting scans every token account of the mint, so nodes that
+    // restrict getProgramAccounts can turn it off with ONCHAIN_HOLDER_COUNT=false.
+    pub fn from_env(known_vault_owners: HashSet<String>) -> Option<Self> {
+        let url = std::env::var("HTTPS_ENDPOINT").ok()?;
+        let count_holders = std::env::var("ONCHAIN_HOLDER_COUNT")
+            .map(|value| !value.eq_ignore_ascii_case("false"))
+            .unwrap_or(true);
+        println!(
+            "⛓️  On-chain mint checks via {} (holder counting {})",
+            url,
+            if count_holders { "on" } else { "off" }
+        );
+        Some(Self::new(url, known_vault_owners, count_holders))
+    }
+
+    pub fn new(url: String, known_vault_owners: HashSet<String>, count_holders: bool) -> Self {
+        Self {
+            rpc: RpcClient::new_with_commitment(url, CommitmentConfig::confirmed()),
+            known_vault_owners,
+            count_holders,
+        }
+    }
+
+    pub async fn fetch_mint(&self, mint_address: &str) -> Result<OnChainMint> {
+        let mint = parse_pubkey(mint_address)?;
+        let account = self
+            .rpc
+            .get_account(&mint)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch mint account {}: {}", mint_address, e))?;
+
+        if account.owner == spl_token::id() {
+            let state = spl_token::state::Mint::unpack(&account.data)
+                .map_err(|e| anyhow!("Failed to decode SPL Token mint {}: {}", mint_address, e))?;
+            Ok(OnChainMint {
+                program: "spl-token".to_string(),
+                mint_authority: Option::from(state.mint_authority).map(|key: Pubkey| key.to_string()),
+                freeze_authority: Option::from(state.freeze_authority).map(|key: Pubkey| key.to_string()),
+                supply: state.supply,
+                decimals: state.decimals,
+                discrepancies: Vec::new(),
+                extensions: Vec::new(),
+                holders: None,
+            })
+        } else if account.owner == spl_token_2022::id() {
+            let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data)
+                .map_err(|e| anyhow!("Failed to decode Token-2022 mint {}: {}", mint_address, e))?;
+            Ok(OnChainMint {
+                program: "token-2022".to_string(),
+                mint_authority: Option::from(state.base.mint_authority).map(|key: Pubkey| key.to_string()),
+                freeze_authority: Option::from(state.base.freeze_authority).map(|key: Pubkey| key.to_string()),
+                supply: state.base.supply,
+                decimals: state.base.decimals,
+                discrepancies: Vec::new(),
+                extensions: decode_extensions(&state),
+                holders: None,
+            })
+        } else {
+            bail!("Account {} is not a token mint (owner {})", mint_address, account.owner)
+        }
+    }
+
+    // `exclusions` are the token accounts or owners that belong to the pool
+    // being analysed
+    pub async fn analyze_holders(
+        &self,
+        mint_address: &str,
+        mint: &OnChainMint,
+        exclusions: &[String],
+    ) -> Result<HolderConcentration> {
+        let mint_key = parse_pubkey(mint_address)?;
+        let is_excluded = |account: &str, owner: &str| {
+            self.known_vault_owners.contains(owner)
+                || exclusions.iter().any(|excluded| excluded == account || excluded == owner)
+        };
+
+        // One owner may hold several accounts
+        let mut balances: HashMap<String, u64> = HashMap::new();
+        let mut excluded_accounts = 0;
+        for holding in self.largest_holdings(mint_address).await? {
+            if is_excluded(&holding.account, &holding.owner) {
+                excluded_accounts += 1;
+            } else if holding.amount > 0 {
+                *balances.entry(holding.owner).or_default() += holding.amount;
+            }
+        }
+
+        let mut largest_holders: Vec<(String, u64)> = balances.into_iter().collect();
+        largest_holders.sort_by_key(|(_, amount)| std::cmp::Reverse(*amount));
+        let share = |amount: u64| {
+            if mint.supply == 0 {
+                0.0
+            } else {
+                amount as f64 / mint.supply as f64 * 100.0
+            }
+        };
+        let largest_holders: Vec<HolderShare> = largest_holders
+            .into_iter()
+            .take(10)
+            .map(|(owner, amount)| HolderShare {
+                owner,
+                percentage: share(amount),
+            })
+            .collect();
+
+        let holder_count = if self.count_holders {
+            match self.count_holders(&mint_key, mint, &is_excluded).await {
+                Ok(count) => Some(count),
+                Err(e) => {
+                    eprintln!("⚠️  Holder count unavailable for {}: {:#}", mint_address, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        Ok(HolderConcentration {
+            top_1_percentage: largest_holders.first().map(|holder| holder.percentage).unwrap_or(0.0),
+            top_10_percentage: largest_holders.iter().map(|holder| holder.percentage).sum(),
+            holder_count,
+            largest_holders,
+            excluded_accounts,
+        })
+    }
+
+    // Current supply of a mint in raw units
+    pub async fn token_supply(&self, mint_address: &str) -> Result<u64> {
+        let supply = self
+            .rpc
+            .get_token_supply(&parse_pubkey(mint_address)?)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch supply of {}: {}", mint_address, e))?;
+        supply
+            .amount
+            .parse()
+            .with_context(|| format!("Invalid supply '{}' for {}", supply.amount, mint_address))
+    }
+
+    // The mint's largest token accounts (at most 20) with their owners
+    pub async fn largest_holdings(&self, mint_address: &str) -> Result<Vec<TokenHolding>> {
+        let largest = self
+            .rpc
+            .get_token_largest_accounts(&parse_pubkey(mint_address)?)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch largest accounts of {}: {}", mint_address, e))?;
+        let account_keys = largest
+            .iter()
+            .map(|balance| parse_pubkey(&balance.address))
+            .collect::<Result<Vec<_>>>()?;
+        let accounts = self
+            .rpc
+            .get_multiple_accounts(&account_keys)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch token accounts of {}: {}", mint_address, e))?;
+
+        Ok(largest
+            .into_iter()
+            .zip(accounts)
+            .filter_map(|(balance, account)| {
+                let (owner, amount) =
+                    token_account_owner_and_amount(account?.data.get(TOKEN_ACCOUNT_OWNER_OFFSET..)?)?;
+                Some(TokenHolding {
+                    account: balance.address,
+                    owner,
+                    amount,
+                })
+            })
+            .collect())
+    }
+
+    // Program owning each of the given accounts; accounts that don't exist are left out
+    pub async fn owner_programs<'a>(
+        &self,
+        addresses: impl Iterator<Item = &'a str>,
+    ) -> Result<HashMap<String, String>> {
+        let addresses: Vec<&str> = addresses.collect();
+        let keys = addresses
+            .iter()
+            .map(|address| parse_pubkey(address))
+            .collect::<Result<Vec<_>>>()?;
+        let accounts = self
+            .rpc
+            .get_multiple_accounts(&keys)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch accounts: {}", e))?;
+
+        Ok(addresses
+            .into_iter()
+            .zip(accounts)
+            .filter_map(|(address, account)| Some((address.to_string(), account?.owner.to_string())))
+            .collect())
+    }
+
+    // Raw amount of `mint_address` held across the accounts a transaction
+    // touched, right after it ran. For the transaction that created a mint's
+    // first tokens (a pool's LP) this is the supply it minted.
+    pub async fn held_after_transaction(&self, signature: &str, mint_address: &str) -> Result<u64> {
+        let signature = Signature::from_str(signature)
+            .map_err(|e| anyhow!("Invalid transaction signature '{}': {}", signature, e))?;
+        let transaction = self
+            .rpc
+            .get_transaction_with_config(
+                &signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(self.rpc.commitment()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await
+            .map_err(|e| anyhow!("Failed to fetch transaction {}: {}", signature, e))?;
+
+        let meta = transaction
+            .transaction
+            .meta
+            .with_context(|| format!("Transaction {} has no status meta", signature))?;
+        let balances: Option<Vec<_>> = meta.post_token_balances.into();
+        let balances = balances.with_context(|| format!("Transaction {} has no token balances", signature))?;
+
+        balances
+            .iter()
+            .filter(|balance| balance.mint == mint_address)
+            .try_fold(0u64, |total, balance| {
+                let amount: u64 = balance.ui_token_amount.amount.parse().with_context(|| {
+                    format!("Invalid token balance '{}' in {}", balance.ui_token_amount.amount, signature)
+                })?;
+                Ok(total.saturating_add(amount))
+            })
+    }
+
+    // Balance of a token account in raw units; a closed account holds nothing
+    pub async fn token_account_amount(&self, account_address: &str) -> Result<u64> {
+        let account = self
+            .rpc
+            .get_account_with_commitment(&parse_pubkey(account_address)?, self.rpc.commitment())
+            .await
+            .map_err(|e| anyhow!("Failed to fetch token account {}: {}", account_address, e))?
+            .value;
+
+        match account {
+            Some(account) => account
+                .data
+                .get(TOKEN_ACCOUNT_OWNER_OFFSET..)
+                .and_then(token_account_owner_and_amount)
+                .map(|(_, amount)| amount)
+                .with_context(|| format!("{} is not a token account", account_address)),
+            None => Ok(0),
+        }
+    }
+
+    // Fetches only owner and amount of every token account of the mint
+    async fn count_holders(
+        &self,
+        mint_key: &Pubkey,
+        mint: &OnChainMint,
+        is_excluded: &(dyn Fn(&str, &str) -> bool + Sync),
+    ) -> Result<u32> {
+        let mut filters = vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, mint_key.as_ref()))];
+        let program_id = if mint.program == "token-2022" {
+            spl_token_2022::id()
+        } else {
+            filters.push(RpcFilterType::DataSize(SPL_TOKEN_ACCOUNT_LEN));
+            spl_token::id()
+        };
+
+        let accounts = self
+            .rpc
+            .get_program_accounts_with_config(
+                &program_id,
+                RpcProgramAccountsConfig {
+                    filters: Some(filters),
+                    account_config: RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        data_slice: Some(UiDataSliceConfig {
+                            offset: TOKEN_ACCOUNT_OWNER_OFFSET,
+                            length: TOKEN_ACCOUNT_AMOUNT_OFFSET + 8 - TOKEN_ACCOUNT_OWNER_OFFSET,
+                        }),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| anyhow!("Token account scan failed: {}", e))?;
+
+        let owners: HashSet<String> = accounts
+            .iter()
+            .filter_map(|(address, account)| {
+                let (owner, amount) = token_account_owner_and_amount(&account.data)?;
+                (amount > 0 && !is_excluded(&address.to_string(), &owner)).then_some(owner)
+            })
+            .collect();
+
+        Ok(owners.len() as u32)
+    }
+}
+
+pub fn parse_pubkey(address: &str) -> Result<Pubkey> {
+    Pubkey::from_str(address).with_context(|| format!("Invalid address '{}'", address))
+}
+
+// Reads owner and amount from token account data starting at the owner field
+fn token_account_owner_and_amount(data: &[u8]) -> Option<(String, u64)> {
+    let owner = Pubkey::try_from(data.get(..32)?).ok()?;
+    let amount = u64::from_le_bytes(data.get(32..40)?.try_into().ok()?);
+    Some((owner.to_string(), amount))
+}
+
+// Only the extensions we consider risky are decoded; anything else on the mint
+// (metadata, pointers, interest) is ignored
+fn decode_extensions(state: &StateWithExtensions<spl_token_2022::state::Mint>) -> Vec<TokenExtension> {
+    let mut extensions = Vec::new();
+
+    if let Ok(config) = state.get_extension::<TransferFeeConfig>() {
+        let older = &config.older_transfer_fee;
+        let newer = &config.newer_transfer_fee;
+        extensions.push(TokenExtension::TransferFee {
+            basis_points: u16::from(older.transfer_fee_basis_points)
+                .max(u16::from(newer.transfer_fee_basis_points)),
+            maximum_fee: u64::from(older.maximum_fee).max(u64::from(newer.maximum_fee)),
+            authority: optional_key(config.transfer_fee_config_authority),
+        });
+    }
+    if let Ok(hook) = state.get_extension::<TransferHook>() {
+        extensions.push(TokenExtension::TransferHook {
+            program_id: optional_key(hook.program_id),
+            authority: optional_key(hook.authority),
+        });
+    }
+    if let Ok(delegate) = state.get_extension::<PermanentDelegate>() {
+        extensions.push(TokenExtension::PermanentDelegate {
+            delegate: optional_key(delegate.delegate),
+        });
+    }
+    if state.get_extension::<NonTransferable>().is_ok() {
+        extensions.push(TokenExtension::NonTransferable);
+    }
+    if let Ok(default_state) = state.get_extension::<DefaultAccountState>()
+        && default_state.state == AccountState::Frozen as u8
+    {
+        extensions.push(TokenExtension::DefaultAccountFrozen);
+    }
+    if let Ok(close_authority) = state.get_extension::<MintCloseAuthority>() {
+        extensions.push(TokenExtension::MintCloseAuthority {
+            authority: optional_key(close_authority.close_authority),
+        });
+    }
+
+    extensions
+}
+
+fn optional_key(key: impl Into<Option<Pubkey>>) -> Option<String> {
+    key.into().map(|key| key.to_string())
+}
+
+// The chain is authoritative for authorities, supply and decimals: the report's
+// values are replaced and any disagreement is recorded on the mint and as an
+// audit risk
+pub fn apply_onchain_mint(token_data: &mut TokenData, mut mint: OnChainMint) {
+    let mint_disabled = mint.mint_authority.is_none();
+    let freeze_disabled = mint.freeze_authority.is_none();
+
+    if token_data.mint_disabled != mint_disabled {
+        mint.discrepancies.push(format!(
+            "report says mint authority is {}, chain says {}",
+            authority_state(token_data.mint_disabled),
+            authority_state(mint_disabled)
+        ));
+    }
+    if token_data.freeze_disabled != freeze_disabled {
+        mint.discrepancies.push(format!(
+            "report says freeze authority is {}, chain says {}",
+            authority_state(token_data.freeze_disabled),
+            authority_state(freeze_disabled)
+        ));
+    }
+    if token_data.decimals != mint.decimals {
+        mint.discrepancies.push(format!(
+            "report says {} decimals, chain says {}",
+            token_data.decimals, mint.decimals
+        ));
+    }
+
+    let supply_amount = mint.supply_amount();
+    if token_data.supply_amount > 0.0
+        && (token_data.supply_amount - supply_amount).abs() > supply_amount * SUPPLY_TOLERANCE
+    {
+        mint.discrepancies.push(format!(
+            "report says supply {:.2}, chain says {:.2}",
+            token_data.supply_amount, supply_amount
+        ));
+    }
+
+    for discrepancy in &mint.discrepancies {
+        println!("⚠️  On-chain mismatch for {}: {}", token_data.address, discrepancy);
+        token_data
+            .audit_risks
+            .push(format!("On-chain mismatch: {}", discrepancy));
+    }
+
+    token_data.mint_disabled = mint_disabled;
+    token_data.freeze_disabled = freeze_disabled;
+    token_data.decimals = mint.decimals;
+    token_data.supply_amount = supply_amount;
+    apply_extension_risk(token_data, &mint.extensions);
+    if let Some(holders) = &mint.holders {
+        apply_holder_concentration(token_data, holders);
+    }
+    token_data.onchain = Some(mint);
+}
+
+// Token data from the mint alone, for degraded mode without a risk provider.
+// The rules are not applied since most fields they read are placeholders, so
+// the risk stays Unknown unless an extension makes the token Critical.
+pub fn onchain_token_data(address: &str, mint: OnChainMint) -> TokenData {
+    let mut token_data = TokenData {
+        address: address.to_string(),
+        token_name: "Unknown".to_string(),
+        token_symbol: "UNKNOWN".to_string(),
+        decimals: mint.decimals,
+        market_cap: 0.0,
+        score: 0,
+        risk_level: RiskLevel::Unknown,
+        price: 0.0,
+        supply_amount: mint.supply_amount(),
+        liquidity_total: 0.0,
+        top_10_percentage: 0.0,
+        holder_count: 0,
+        is_honeypot: false,
+        high_risk_count: 0,
+        audit_risks: vec!["No risk provider configured - on-chain checks only".to_string()],
+        deploy_time: "Unknown".to_string(),
+        mint_disabled: mint.mint_authority.is_none(),
+        freeze_disabled: mint.freeze_authority.is_none(),
+        lp_burned: false,
+        analysis_status: AnalysisStatus::OnChainOnly,
+        provider_scores: Vec::new(),
+        onchain: None,
+        fired_rules: Vec::new(),
+    };
+    if mint
+        .extensions
+        .iter()
+        .any(|extension| extension.severity() == RiskLevel::Critical)
+    {
+        token_data.risk_level = RiskLevel::Critical;
+    }
+
+    apply_onchain_mint(&mut token_data, mint);
+    token_data
+}
+
+// Holder figures for one pool's exclusions, applied on top of the mint state
+// (which may come from the cache)
+pub fn apply_holders(token_data: &mut TokenData, holders: HolderConcentration) {
+    apply_holder_concentration(token_data, &holders);
+    if let Some(mint) = &mut token_data.onchain {
+        mint.holders = Some(holders);
+    }
+}
+
+// Replaces the report's holder figures, which only cover the provider's page
+// of owners and count pool vaults as holders
+fn apply_holder_concentration(token_data: &mut TokenData, holders: &HolderConcentration) {
+    token_data.top_10_percentage = holders.top_10_percentage;
+    if let Some(holder_count) = holders.holder_count {
+        token_data.holder_count = holder_count;
+    }
+
+    if holders.top_1_percentage >= TOP_1_ALERT_PCT {
+        token_data.audit_risks.push(format!(
+            "On-chain: largest holder owns {:.2}% of supply",
+            holders.top_1_percentage
+        ));
+    }
+    if holders.top_10_percentage >= TOP_10_ALERT_PCT {
+        token_data.audit_risks.push(format!(
+            "On-chain: top 10 holders own {:.2}% of supply",
+            holders.top_10_percentage
+        ));
+    }
+}
+
+// Extensions can only make a verdict worse: the score is capped at the top of
+// the band for the worst extension's severity
+fn apply_extension_risk(token_data: &mut TokenData, extensions: &[TokenExtension]) {
+    for extension in extensions {
+        let severity = extension.severity();
+        if severity != RiskLevel::Low {
+            token_data
+                .audit_risks
+                .push(format!("Token-2022 {:?}: {}", severity, extension.describe()));
+        }
+        if extension.blocks_selling() {
+            token_data.is_honeypot = true;
+        }
+
+        let score_cap = match severity {
+            RiskLevel::Critical => 25,
+            RiskLevel::High => 50,
+            RiskLevel::Medium => 75,
+            RiskLevel::Low | RiskLevel::Unknown => continue,
+        };
+        if token_data.score > score_cap {
+            token_data.score = score_cap;
+            token_data.risk_level = RiskLevel::from_score(score_cap);
+        }
+    }
+}
+
+fn authority_state(disabled: bool) -> &'static str {
+    if disabled { "revoked" } else { "active" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mint(extensions: Vec<TokenExtension>) -> OnChainMint {
+        OnChainMint {
+            program: "token-2022".to_string(),
+            mint_authority: Some("11111111111111111111111111111111".to_string()),
+            freeze_authority: None,
+            supply: 1_000_000_000_000,
+            decimals: 6,
+            discrepancies: Vec::new(),
+            extensions,
+            holders: None,
+        }
+    }
+
+    #[test]
+    fn onchain_token_data_takes_mint_state_without_discrepancies() {
+        let token_data = onchain_token_data("Mint", mint(Vec::new()));
+        assert_eq!(token_data.analysis_status, AnalysisStatus::OnChainOnly);
+        assert_eq!(token_data.known_decimals(), Some(6));
+        assert_eq!(token_data.supply_amount, 1_000_000.0);
+        assert!(!token_data.mint_disabled);
+        assert!(token_data.freeze_disabled);
+        assert_eq!(token_data.risk_level, RiskLevel::Unknown);
+        assert!(!token_data.is_honeypot);
+        assert!(token_data.onchain.is_some_and(|mint| mint.discrepancies.is_empty()));
+    }
+
+    #[test]
+    fn onchain_token_data_flags_extensions_that_block_selling() {
+        let token_data = onchain_token_data("Mint", mint(vec![TokenExtension::NonTransferable]));
+        assert_eq!(token_data.risk_level, RiskLevel::Critical);
+        assert!(token_data.is_honeypot);
+    }
+}
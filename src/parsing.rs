@@ -1,22 +1,231 @@
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
 
+use crate::dexes::{DexAccounts, DexArguments, DexDefinition, DexRegistry, LaunchAction};
+use crate::launches::*;
+use crate::liquidity::{Coin, LiquidityConfig, QuoteLiquidity, QuoteToken, WSOL_MINT};
 use crate::solsniffer::*;
+use crate::lp::LpStatus;
+use crate::risk::TokenAnalyzer;
+use crate::rules::PoolVerdict;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PoolCreationEvent {
     pub dex_name: String,
+    pub pool_type: Option<String>,
     pub pool_address: Option<String>,
     pub token_a: TokenData,
     pub token_b: TokenData,
     pub timestamp: String,
     pub transaction_signature: String,
     pub liquidity_amounts: LiquidityInfo,
+    pub pool_params: PoolParameters,
     pub coin_type: Coin,
+    // Bonding-curve launch of one of the pool's tokens, when we saw it
+    pub launch: Option<LaunchLink>,
+    // Reserve, LP mint and authority accounts of the pool
+    #[serde(default)]
+    pub pool_vaults: Vec<String>,
+    // Fate of the LP tokens minted with the pool, for DEXes that mint them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lp: Option<LpStatus>,
+    // Pool-scoped rules that fired, recomputed before every publish
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<PoolVerdict>,
+    // Launch price and liquidity in SOL and USD, when a price feed is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valuation: Option<PoolValuation>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl PoolCreationEvent {
+    // A pool is as risky as the riskier of its two tokens; a known Critical
+    // token outranks an unanalysed one. Pool rules can only raise the level.
+    pub fn risk_level(&self) -> RiskLevel {
+        let pool_level = self.verdict.as_ref().map(|verdict| &verdict.risk_level);
+        let levels = [Some(&self.token_a.risk_level), Some(&self.token_b.risk_level), pool_level];
+        if levels.contains(&Some(&RiskLevel::Critical)) {
+            RiskLevel::Critical
+        } else {
+            levels.into_iter().flatten().max().cloned().unwrap_or(RiskLevel::Unknown)
+        }
+    }
+
+    // Accounts whose balances belong to the pool (or the curve it came from)
+    // rather than to holders
+    pub fn holder_exclusions(&self) -> Vec<String> {
+        self.pool_address
+            .iter()
+            .chain(&self.pool_vaults)
+            .chain(self.launch.as_ref().map(|launch| &launch.bonding_curve))
+            .cloned()
+            .collect()
+    }
+
+    pub fn is_honeypot(&self) -> bool {
+        self.token_a.is_honeypot
+            || self.token_b.is_honeypot
+            || self.verdict.as_ref().is_some_and(|verdict| verdict.is_honeypot)
+    }
+
+    // Quote side of the initial deposit valued in SOL; events stored before
+    // quotes were tracked only have the raw SOL amount
+    pub fn liquidity_sol(&self) -> Option<f64> {
+        if let Some(quote) = &self.liquidity_amounts.quote {
+            return Some(quote.value_sol);
+        }
+        let raw = self.liquidity_amounts.sol_amount.as_ref()?.parse::<u64>().ok()?;
+        Some(raw as f64 / 1_000_000_000.0)
+    }
+
+    // True once neither token is still waiting for its analysis
+    pub fn is_enriched(&self) -> bool {
+        self.token_a.analysis_status != AnalysisStatus::Pending
+            && self.token_b.analysis_status != AnalysisStatus::Pending
+    }
+
+    // Formatted amounts and the initial price need token decimals, which are
+    // only known once a token has been analysed
+    pub fn refresh_derived_fields(&mut self) {
+        let decimals_a = self.token_a.known_decimals();
+        let decimals_b = self.token_b.known_decimals();
+        let amounts = &mut self.liquidity_amounts;
+
+        amounts.token_a_amount_formatted = amounts
+            .token_a_amount
+            .as_ref()
+            .zip(decimals_a)
+            .map(|(amount, decimals)| format_token_amount(amount, decimals));
+        amounts.token_b_amount_formatted = amounts
+            .token_b_amount
+            .as_ref()
+            .zip(decimals_b)
+            .map(|(amount, decimals)| format_token_amount(amount, decimals));
+        amounts.sol_amount_formatted = amounts
+            .sol_amount
+            .as_ref()
+            .map(|amount| format_token_amount(amount, 9)); // SOL has 9 decimals
+
+        if let (Some(decimals_a), Some(decimals_b)) = (decimals_a, decimals_b) {
+            let params = &mut self.pool_params;
+            params.initial_price = match (&params.sqrt_price_x64, params.bin_step, params.active_id) {
+                (Some(raw), _, _) => raw
+                    .parse::<u128>()
+                    .ok()
+                    .map(|sqrt_price| sqrt_price_x64_to_price(sqrt_price, decimals_a, decimals_b)),
+                (None, Some(bin_step), Some(active_id)) => {
+                    Some(active_bin_price(bin_step, active_id, decimals_a, decimals_b))
+                }
+                _ => None,
+            };
+
+            if let Some(price) = params.initial_price {
+                println!(
+                    "💱 Initial price: {} (tick spacing {:?}, bin step {:?})",
+                    price, params.tick_spacing, params.bin_step
+                );
+            }
+        }
+    }
+
+    // Re-tiers the pool and values the launch in SOL. Stable quotes are
+    // converted at the live SOL/USD rate when there is one (the reference rate
+    // otherwise), and only a live rate fills in the USD figures.
+    pub fn revalue(&mut self, liquidity: &LiquidityConfig, sol_usd: Option<f64>) {
+        self.liquidity_amounts.quote = liquidity.quote_liquidity(
+            (&self.token_a.address, &self.liquidity_amounts.token_a_amount),
+            (&self.token_b.address, &self.liquidity_amounts.token_b_amount),
+            sol_usd,
+        );
+        self.coin_type = liquidity.classify(self.liquidity_sol());
+
+        let Some((quote, quote_is_b)) = liquidity.quote_side(&self.token_a.address, &self.token_b.address) else {
+            self.valuation = None;
+            return;
+        };
+        let amounts = &self.liquidity_amounts;
+        let (base, base_amount, quote_amount) = if quote_is_b {
+            (&self.token_a, &amounts.token_a_amount, &amounts.token_b_amount)
+        } else {
+            (&self.token_b, &amounts.token_b_amount, &amounts.token_a_amount)
+        };
+        let quote_sol = liquidity.quote_value_sol(quote, sol_usd);
+
+        let units = |raw: &Option<String>, decimals: Option<u8>| {
+            let raw = raw.as_ref()?.parse::<u64>().ok()?;
+            Some(raw as f64 / 10f64.powi(decimals? as i32))
+        };
+        let base_units = units(base_amount, base.known_decimals()).filter(|units| *units > 0.0);
+        let quote_units = units(quote_amount, Some(quote.decimals));
+
+        // Deposit ratio first; pools created without a deposit have an initial price
+        let price_in_quote = match (base_units, quote_units) {
+            (Some(base_units), Some(quote_units)) => Some(quote_units / base_units),
+            _ => self.pool_params.initial_price.filter(|price| *price > 0.0).map(|price| {
+                // initial_price is token B per token A
+                if quote_is_b { price } else { 1.0 / price }
+            }),
+        };
+        let token_price_sol = price_in_quote.map(|price| price * quote_sol);
+        let token_price_usd = token_price_sol.zip(sol_usd).map(|(price, sol_usd)| price * sol_usd);
+        let fdv_usd = token_price_usd
+            .zip(base.onchain.as_ref())
+            .map(|(price, mint)| price * mint.supply_amount());
+        let liquidity_usd = match (base_units, quote_units, token_price_usd, sol_usd) {
+            (Some(base_units), Some(quote_units), Some(price), Some(sol_usd)) => {
+                Some(quote_units * quote_sol * sol_usd + base_units * price)
+            }
+            _ => None,
+        };
+
+        self.valuation = Some(PoolValuation {
+            sol_usd,
+            base_mint: base.address.clone(),
+            token_price_sol,
+            token_price_usd,
+            fdv_usd,
+            liquidity_usd,
+        });
+    }
+}
+
+// Everything an instruction in the combined subscription can turn into
+#[derive(Debug, Clone)]
+pub enum ParsedEvent {
+    PoolCreation(Box<PoolCreationEvent>),
+    BondingCurveCreated(BondingCurveEvent),
+    BondingCurveMigrated(MigrationEvent),
+}
+
+// Pool-type specific launch parameters; only the fields the DEX reports are set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PoolParameters {
+    pub sqrt_price_x64: Option<String>,
+    // Token B per token A, decimals applied
+    pub initial_price: Option<f64>,
+    pub tick_spacing: Option<u16>,
+    pub fee_tier: Option<String>,
+    pub fee_rate_bps: Option<f64>,
+    pub bin_step: Option<u16>,
+    pub active_id: Option<i32>,
+}
+
+// What the non-quote (base) token launched at. Prices need the base token's
+// decimals and the FDV its on-chain supply, so those fill in after analysis.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolValuation {
+    // Rate the USD figures were worked out at; None without a price feed
+    pub sol_usd: Option<f64>,
+    pub base_mint: String,
+    pub token_price_sol: Option<f64>,
+    pub token_price_usd: Option<f64>,
+    // Initial fully diluted valuation
+    pub fdv_usd: Option<f64>,
+    // Both sides of the initial deposit
+    pub liquidity_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LiquidityInfo {
     pub token_a_amount: Option<String>,
     pub token_b_amount: Option<String>,
@@ -25,6 +234,9 @@ pub struct LiquidityInfo {
     pub token_a_amount_formatted: Option<String>,
     pub token_b_amount_formatted: Option<String>,
     pub sol_amount_formatted: Option<String>,
+    // Quote side of the deposit (SOL or a configured stable), valued in SOL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote: Option<QuoteLiquidity>,
 }
 
 pub fn format_token_amount(raw_amount: &str, decimals: u8) -> String {
@@ -46,129 +258,25 @@ pub fn format_token_amount(raw_amount: &str, decimals: u8) -> String {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub enum Coin {
-    ImmediateCoin,
-    ShortTermCoin,
-    GenuineCoin,
-    MegaCoin,
+// Why a single instruction in a batch could not be turned into an event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstructionError {
+    pub index: usize,
+    pub signature: Option<String>,
+    pub reason: String,
 }
 
-impl Coin {
-    pub fn coin_class(liquidity: f32) -> Self {
-        match liquidity {
-            x if x < 120.0 => Coin::ImmediateCoin,
-            x if x >= 120.0 && x < 200.0 => Coin::ShortTermCoin,
-            x if x >= 200.0 && x <= 4000.0 => Coin::GenuineCoin,
-            x if x > 4000.0 => Coin::MegaCoin,
-            _ => {
-                eprintln!(
-                    "Warning: Unhandled or invalid liquidity value encountered: {}",
-                    liquidity
-                );
-                Coin::ImmediateCoin
-            }
-        }
-    }
-}
-
-// Configuration for each DEX
-#[derive(Debug)]
-struct DexConfig {
-    name: String,
-    method_name: String,
-    token_a_account: String,
-    token_b_account: String,
-    pool_account: String,
-    token_a_amount_arg: String,
-    token_b_amount_arg: String,
-    token_a_key: String,
-    token_b_key: String,
-}
-
-impl DexConfig {
-    fn get_config(dex_name: &str) -> Option<Self> {
-        match dex_name {
-            "raydium" => Some(DexConfig {
-                name: "Raydium".to_string(),
-                method_name: "initialize2".to_string(),
-                token_a_account: "pcMint".to_string(),
-                token_b_account: "coinMint".to_string(),
-                pool_account: "amm".to_string(),
-                token_a_amount_arg: "initPcAmount".to_string(),
-                token_b_amount_arg: "initCoinAmount".to_string(),
-                token_a_key: "pc_mint".to_string(),
-                token_b_key: "coin_mint".to_string(),
-            }),
-            "pumpswap" => Some(DexConfig {
-                name: "PumpSwap".to_string(),
-                method_name: "create_pool".to_string(),
-                token_a_account: "base_mint".to_string(),
-                token_b_account: "quote_mint".to_string(),
-                pool_account: "pool".to_string(),
-                token_a_amount_arg: "base_amount_in".to_string(),
-                token_b_amount_arg: "quote_amount_in".to_string(),
-                token_a_key: "base_mint".to_string(),
-                token_b_key: "quote_mint".to_string(),
-            }),
-            "meteora" => Some(DexConfig {
-                name: "Meteora".to_string(),
-                method_name: "initializePermissionlessConstantProductPoolWithConfig2".to_string(),
-                token_a_account: "tokenAMint".to_string(),
-                token_b_account: "tokenBMint".to_string(),
-                pool_account: "pool".to_string(),
-                token_a_amount_arg: "tokenAAmount".to_string(),
-                token_b_amount_arg: "tokenBAmount".to_string(),
-                token_a_key: "token_a_mint".to_string(),
-                token_b_key: "token_b_mint".to_string(),
-            }),
-            _ => None,
-        }
-    }
-}
-
-// Made this function async since it calls parse_dex_event which is async
-pub async fn parse_pool_creation_event(
+// Parses the payload of a subscription `next` message; protocol control
+// messages never reach here. Bitquery may batch several instructions into one
+// message, so every instruction yields its own event or error. Returns None
+// when the payload carries no instructions at all.
+pub fn parse_pool_creation_event(
+    registry: &DexRegistry,
     subscription_name: &str,
-    data: &Value,
-) -> Option<PoolCreationEvent> {
+    payload: &Value,
+) -> Option<Vec<Result<ParsedEvent, InstructionError>>> {
     println!("🔍 Parsing data for {}", subscription_name);
 
-    if let Some(msg_type) = data.get("type").and_then(|t| t.as_str()) {
-        match msg_type {
-            "pong" | "connection_ack" | "connection_error" | "ka" => {
-                println!(
-                    "⏭️  Skipping control message '{}' for {} \n",
-                    msg_type, subscription_name
-                );
-                return None;
-            }
-            "next" => {
-                println!("✅ Processing data message for {}", subscription_name);
-            }
-            _ => {
-                println!(
-                    "🔍 Unknown message type '{}' for {}",
-                    msg_type, subscription_name
-                );
-            }
-        }
-    }
-
-    let payload = if let Some(payload) = data.get("payload") {
-        println!("Got Payload");
-        payload
-    } else if let Some(data_field) = data.get("data") {
-        println!("Got data_field");
-        data_field
-    } else {
-        println!(
-            "❌ No payload or data field found for {}",
-            subscription_name
-        );
-        return None;
-    };
-
     let instructions = if let Some(solana_data) = payload.get("data").and_then(|d| d.get("Solana"))
     {
         solana_data.get("Instructions")?.as_array()?
@@ -187,213 +295,192 @@ pub async fn parse_pool_creation_event(
         return None;
     }
 
-    let instruction = &instructions[0];
-    let block = instruction.get("Block")?;
-    let transaction = instruction.get("Transaction")?;
-    let instruction_data = instruction.get("Instruction")?;
+    println!(
+        "📦 {} instruction(s) in batch for {}",
+        instructions.len(),
+        subscription_name
+    );
 
-    let timestamp = block.get("Time")?.as_str()?.to_string();
-    let signature = transaction.get("Signature")?.as_str()?.to_string();
+    let mut results = Vec::with_capacity(instructions.len());
+    for (index, instruction) in instructions.iter().enumerate() {
+        let signature = instruction
+            .get("Transaction")
+            .and_then(|t| t.get("Signature"))
+            .and_then(|s| s.as_str())
+            .map(|s| s.to_string());
+
+        match parse_instruction(registry, subscription_name, instruction) {
+            Ok(event) => {
+                println!(
+                    "✅ Successfully parsed event #{} for {}",
+                    index, subscription_name
+                );
+                results.push(Ok(event));
+            }
+            Err(e) => {
+                println!(
+                    "❌ Failed to parse instruction #{} for {}: {}",
+                    index, subscription_name, e
+                );
+                results.push(Err(InstructionError {
+                    index,
+                    signature,
+                    reason: e.to_string(),
+                }));
+            }
+        }
+    }
+
+    Some(results)
+}
+
+fn parse_instruction(
+    registry: &DexRegistry,
+    subscription_name: &str,
+    instruction: &Value,
+) -> Result<ParsedEvent> {
+    let block = instruction.get("Block").context("Missing Block")?;
+    let transaction = instruction.get("Transaction").context("Missing Transaction")?;
+    let instruction_data = instruction.get("Instruction").context("Missing Instruction")?;
+
+    let timestamp = block
+        .get("Time")
+        .and_then(|t| t.as_str())
+        .context("Missing Block.Time")?
+        .to_string();
+    let signature = transaction
+        .get("Signature")
+        .and_then(|s| s.as_str())
+        .context("Missing Transaction.Signature")?
+        .to_string();
 
     println!(
         "✅ Found instruction data for {}: timestamp={}, signature={}",
         subscription_name, timestamp, signature
     );
 
-    // Now properly await the async function
-    let result = parse_dex_event(subscription_name, instruction_data, timestamp, signature).await;
-
-    if result.is_some() {
-        println!(
-            "✅ Successfully parsed pool creation event for {}",
-            subscription_name
-        );
-    } else {
-        println!(
-            "❌ Failed to parse pool creation event for {}",
-            subscription_name
-        );
+    // Launchpad instructions share the subscription with pool creations
+    let program = instruction_data.get("Program");
+    let program_address = program.and_then(|p| p.get("Address")).and_then(|a| a.as_str());
+    let method_name = program
+        .and_then(|p| p.get("Method"))
+        .and_then(|m| m.as_str())
+        .unwrap_or("");
+
+    if let Some((launchpad, action)) = registry.find_launchpad(program_address, method_name) {
+        println!("🚀 {} {:?} instruction: {}", launchpad.name, action, method_name);
+        return match action {
+            LaunchAction::Create => {
+                parse_bonding_curve_create(launchpad, instruction_data, timestamp, signature)
+                    .map(ParsedEvent::BondingCurveCreated)
+            }
+            LaunchAction::Migrate => {
+                parse_bonding_curve_migration(launchpad, instruction_data, timestamp, signature)
+                    .map(ParsedEvent::BondingCurveMigrated)
+            }
+        };
     }
 
-    result
+    parse_dex_event(registry, instruction_data, timestamp, signature)
+        .map(|pool_event| ParsedEvent::PoolCreation(Box::new(pool_event)))
 }
 
-async fn parse_dex_event(
-    dex_name: &str,
+fn parse_dex_event(
+    registry: &DexRegistry,
     instruction: &Value,
     timestamp: String,
     signature: String,
-) -> Option<PoolCreationEvent> {
+) -> Result<PoolCreationEvent> {
     // println!("Got data for parsing: {}",serde_json::to_string_pretty(instruction).unwrap_or_default());
 
-    let accounts = instruction.get("Accounts")?.as_array()?;
-    let program = instruction.get("Program")?;
-
-    let program_name = program.get("Name")?.as_str()?;
-    let method_name = program.get("Method")?.as_str().unwrap_or("");
+    let accounts = instruction
+        .get("Accounts")
+        .and_then(|a| a.as_array())
+        .context("Missing Instruction.Accounts")?;
+    let program = instruction.get("Program").context("Missing Instruction.Program")?;
+
+    let program_name = program
+        .get("Name")
+        .and_then(|n| n.as_str())
+        .context("Missing Program.Name")?;
+    let program_address = program.get("Address").and_then(|a| a.as_str());
+    let method_name = program.get("Method").and_then(|m| m.as_str()).unwrap_or("");
     println!("method name:{}", method_name);
 
-    // Handle combined case by matching method name to appropriate config
-    let config = if dex_name == "combined" {
-        // Try to find a config that matches the method name
-        let dex_names = ["raydium", "pumpswap", "meteora"];
-        let mut matching_config = None;
-
-        for &dex in &dex_names {
-            if let Some(test_config) = DexConfig::get_config(dex) {
-                println!("config method name:{}", method_name);
-                if test_config.method_name == method_name {
-                    matching_config = Some(test_config);
-                    break;
-                }
-            }
-        }
-
-        if let Some(config) = matching_config {
-            println!(
-                "🎯 Combined mode: matched method '{}' to {} config",
-                method_name, config.name
-            );
-            config
-        } else {
-            println!(
-                "❌ Combined mode: no config found for method '{}'",
-                method_name
-            );
-            return None;
-        }
-    } else {
-        DexConfig::get_config(dex_name)?
-    };
+    // Match the instruction to a configured DEX by program and method
+    let config = registry.find(program_address, method_name).with_context(|| {
+        format!(
+            "No DEX config for method '{}' on program {}",
+            method_name,
+            program_address.unwrap_or("<unknown>")
+        )
+    })?;
 
     println!(
-        "📋 {} program name: {}, method: {}",
-        config.name, program_name, method_name
+        "📋 {} ({}) program name: {}, method: {}",
+        config.name,
+        config.pool_type.as_deref().unwrap_or("-"),
+        program_name,
+        method_name
     );
 
-    if method_name != config.method_name {
-        println!(
-            "⏭️  Not a {} pool creation instruction: method = {}",
-            config.name, method_name
-        );
-        return None;
-    }
+    let (account_map, argument_map) = config.mapping_for(method_name);
 
     // Extract liquidity amounts from arguments
-    let (token_a_amount, token_b_amount) = extract_liquidity_amounts(program, &config);
+    let (token_a_amount, token_b_amount) = extract_liquidity_amounts(program, argument_map);
     println!(
         "💰 Extracted amounts - Token A: {:?}, Token B: {:?}",
         token_a_amount, token_b_amount
     );
 
     // Extract token addresses and pool address from accounts
-    let (token_addresses, pool_address) =
-        extract_addresses_from_accounts(accounts, program, &config);
+    let pool_accounts = extract_addresses_from_accounts(accounts, program, account_map);
 
     println!(
-        "🔢 Found {} token addresses: {:?}",
-        token_addresses.len(),
-        token_addresses
+        "🪙 Token addresses: A={:?}, B={:?}",
+        pool_accounts.token_a_mint, pool_accounts.token_b_mint
     );
-    println!("🏊 Pool address: {:?}", pool_address);
-
-    // Ensure we have exactly 2 tokens for a pair
-    if token_addresses.len() != 2 {
-        println!(
-            "❌ Expected exactly 2 tokens, found: {}",
-            token_addresses.len()
-        );
-        return None;
-    }
-
-    // Extract token addresses from HashMap
-    let token_a_address = token_addresses.get(&config.token_a_key)?.clone();
-    let token_b_address = token_addresses.get(&config.token_b_key)?.clone();
-
-    let token_data_a: TokenData;
-    let token_data_b: TokenData;
-
-    // Properly handle the Result from sniffer function
-    if token_a_address == "So11111111111111111111111111111111111111112" {
-        token_data_a = create_fallback_for_wsol(&token_a_address)
-    } else {
-        token_data_a = match sniffer(&token_a_address).await {
-            Ok(token_data) => {
-                println!("✅ Analysis complete for token A: {}", token_data.address);
-                token_data
-            }
-            Err(e) => {
-                eprintln!(
-                    "❌ Failed to get token A data for {}: {}",
-                    token_a_address, e
-                );
-                create_fallback_token_data(&token_a_address)
-            }
-        };
-    }
-
-    if token_b_address == "So11111111111111111111111111111111111111112" {
-        token_data_b = create_fallback_for_wsol(&token_b_address)
-    } else {
-        token_data_b = match sniffer(&token_b_address).await {
-            Ok(token_data) => {
-                println!("✅ Analysis complete for token B: {}", token_data.address);
-                token_data
-            }
-            Err(e) => {
-                eprintln!(
-                    "❌ Failed to get token B data for {}: {}",
-                    token_b_address, e
-                );
-                create_fallback_token_data(&token_b_address)
-            }
-        };
-    }
-
-    // Determine SOL amount and convert to f32 for classification
-    let sol_amount = determine_sol_amount(
-        &token_a_address,
-        &token_b_address,
-        &token_a_amount,
-        &token_b_amount,
+    println!("🏊 Pool address: {:?}", pool_accounts.pool);
+
+    // Ensure we have both sides of the pair
+    let token_a_address = pool_accounts
+        .token_a_mint
+        .with_context(|| format!("Missing {} account", account_map.token_a_mint))?;
+    let token_b_address = pool_accounts
+        .token_b_mint
+        .with_context(|| format!("Missing {} account", account_map.token_b_mint))?;
+    let pool_address = pool_accounts.pool;
+
+    // Token analyses arrive later via enrich_pool_event so the pool is published right away
+    let token_data_a = initial_token_data(&registry.liquidity, &token_a_address);
+    let token_data_b = initial_token_data(&registry.liquidity, &token_b_address);
+
+    // Tier the pool by the value of its quote side, in SOL whatever the quote
+    let liquidity = &registry.liquidity;
+    let quote = liquidity.quote_liquidity(
+        (&token_a_address, &token_a_amount),
+        (&token_b_address, &token_b_amount),
+        None,
     );
-
-    let sol_amount_f32: f32 = if let Some(ref sol_str) = sol_amount {
-        // Convert from raw amount to SOL (divide by 10^9)
-        match sol_str.parse::<u64>() {
-            Ok(raw_amount) => {
-                let sol_formatted = raw_amount as f64 / 1_000_000_000.0;
-                sol_formatted as f32
-            }
-            Err(e) => {
-                eprintln!("Failed to parse SOL amount '{}' to u64: {}", sol_str, e);
-                0.0
-            }
-        }
-    } else {
-        eprintln!("No SOL amount found in liquidity pair");
-        0.0
-    };
-
-    println!("💰 SOL amount for classification: {}", sol_amount_f32);
-
-    let coin_type = Coin::coin_class(sol_amount_f32);
-
-    // Format amounts for display
-    let token_a_amount_formatted = token_a_amount
+    let sol_amount = quote
         .as_ref()
-        .map(|amount| format_token_amount(amount, token_data_a.decimals));
+        .filter(|quote| quote.mint == WSOL_MINT)
+        .map(|quote| quote.amount.clone());
+    match &quote {
+        Some(quote) => println!(
+            "💰 Quote liquidity for classification: {} {} ({:.4} SOL)",
+            quote.amount_formatted, quote.symbol, quote.value_sol
+        ),
+        None => eprintln!("No quote token amount found in liquidity pair"),
+    }
 
-    let token_b_amount_formatted = token_b_amount
-        .as_ref()
-        .map(|amount| format_token_amount(amount, token_data_b.decimals));
+    let coin_type = liquidity.classify(quote.as_ref().map(|quote| quote.value_sol));
 
-    let sol_amount_formatted = sol_amount.as_ref().map(|amount| {
-        format_token_amount(amount, 9) // SOL has 9 decimals
-    });
+    let pool_params = extract_pool_parameters(program, config, argument_map, pool_accounts.fee_tier);
 
-    Some(PoolCreationEvent {
-        dex_name: config.name,
+    let mut pool_event = PoolCreationEvent {
+        dex_name: config.name.clone(),
+        pool_type: config.pool_type.clone(),
         pool_address,
         token_a: token_data_a,
         token_b: token_data_b,
@@ -403,16 +490,79 @@ async fn parse_dex_event(
             token_a_amount: token_a_amount.clone(),
             token_b_amount: token_b_amount.clone(),
             sol_amount,
-            token_a_amount_formatted,
-            token_b_amount_formatted,
-            sol_amount_formatted,
+            token_a_amount_formatted: None,
+            token_b_amount_formatted: None,
+            sol_amount_formatted: None,
+            quote,
         },
+        pool_params,
         coin_type,
-    })
+        launch: None,
+        pool_vaults: pool_accounts.vaults,
+        lp: pool_accounts
+            .lp_mint
+            .map(|lp_mint| LpStatus::new(lp_mint, pool_accounts.creator_lp_account)),
+        verdict: None,
+        valuation: None,
+    };
+    pool_event.refresh_derived_fields();
+
+    Ok(pool_event)
+}
+
+// Runs both token analyses concurrently and fills in everything that depends
+// on them. Tokens that need no analysis (quote tokens) are left as they are.
+pub async fn enrich_pool_event(analyzer: &TokenAnalyzer, mut pool_event: PoolCreationEvent) -> PoolCreationEvent {
+    let exclusions = pool_event.holder_exclusions();
+    let (token_a, token_b) = tokio::join!(
+        analyze_pool_token(analyzer, &pool_event.token_a, "A", &exclusions),
+        analyze_pool_token(analyzer, &pool_event.token_b, "B", &exclusions),
+    );
+    pool_event.token_a = token_a;
+    pool_event.token_b = token_b;
+    pool_event.refresh_derived_fields();
+
+    pool_event
+}
+
+// Quote tokens (WSOL, stables) are known up front; every other token waits for its analysis
+fn initial_token_data(liquidity: &LiquidityConfig, address: &str) -> TokenData {
+    match liquidity.quote(address) {
+        Some(quote) => create_quote_token_data(quote),
+        None => create_fallback_token_data(address, AnalysisStatus::Pending, "Analysis pending"),
+    }
+}
+
+async fn analyze_pool_token(
+    analyzer: &TokenAnalyzer,
+    token: &TokenData,
+    side: &str,
+    exclusions: &[String],
+) -> TokenData {
+    if token.analysis_status != AnalysisStatus::Pending {
+        return token.clone();
+    }
+    let address = token.address.as_str();
+
+    match analyzer.analyze(address, exclusions).await {
+        Ok(token_data) => {
+            println!("✅ Analysis complete for token {}: {}", side, token_data.address);
+            token_data
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to get token {} data for {}: {}", side, address, e);
+            create_fallback_token_data(
+                address,
+                AnalysisStatus::Unavailable,
+                &format!("Unable to analyze - {}", e),
+            )
+        }
+    }
 }
 
-// Helper function to create fallback TokenData when API fails
-fn create_fallback_token_data(address: &str) -> TokenData {
+// Placeholder TokenData while no analysis is available. The risk is Unknown
+// rather than a guessed verdict; analysis_status says why.
+fn create_fallback_token_data(address: &str, analysis_status: AnalysisStatus, reason: &str) -> TokenData {
     TokenData {
         address: address.to_string(),
         token_name: "Unknown".to_string(),
@@ -420,27 +570,32 @@ fn create_fallback_token_data(address: &str) -> TokenData {
         decimals: 9,
         market_cap: 0.0,
         score: 0,
-        risk_level: RiskLevel::Critical, // Mark as critical when we can't analyze
+        risk_level: RiskLevel::Unknown,
         price: 0.0,
         supply_amount: 0.0,
         liquidity_total: 0.0,
         top_10_percentage: 0.0,
         holder_count: 0,
-        is_honeypot: true, // Mark as honeypot when we can't verify
-        audit_risks: vec!["Unable to analyze - API error".to_string()],
+        is_honeypot: false,
+        high_risk_count: 0,
+        audit_risks: vec![reason.to_string()],
         deploy_time: "Unknown".to_string(),
         mint_disabled: false,
         freeze_disabled: false,
         lp_burned: false,
+        analysis_status,
+        provider_scores: Vec::new(),
+        onchain: None,
+        fired_rules: Vec::new(),
     }
 }
 
-fn create_fallback_for_wsol(address: &str) -> TokenData {
+fn create_quote_token_data(quote: &QuoteToken) -> TokenData {
     TokenData {
-        address: address.to_string(),
-        token_name: "Wrapped SOL".to_string(),
-        token_symbol: "WSOL".to_string(),
-        decimals: 9,
+        address: quote.mint.clone(),
+        token_name: quote.name.clone().unwrap_or_else(|| quote.symbol.clone()),
+        token_symbol: quote.symbol.clone(),
+        decimals: quote.decimals,
         market_cap: 0.0,
         score: 100,
         risk_level: RiskLevel::Low,
@@ -450,59 +605,125 @@ fn create_fallback_for_wsol(address: &str) -> TokenData {
         top_10_percentage: 0.0,
         holder_count: 0,
         is_honeypot: false,
+        high_risk_count: 0,
         audit_risks: vec!["No risk".to_string()],
         deploy_time: "Unknown".to_string(),
         mint_disabled: false,
         freeze_disabled: false,
         lp_burned: false,
+        analysis_status: AnalysisStatus::Trusted,
+        provider_scores: Vec::new(),
+        onchain: None,
+        fired_rules: Vec::new(),
     }
 }
 
 fn extract_liquidity_amounts(
     program: &Value,
-    config: &DexConfig,
+    arguments: &DexArguments,
 ) -> (Option<String>, Option<String>) {
-    let mut token_a_amount = None;
-    let mut token_b_amount = None;
-
-    if let Some(arguments) = program.get("Arguments").and_then(|a| a.as_array()) {
-        for arg in arguments {
-            if let Some(name) = arg.get("Name").and_then(|n| n.as_str()) {
-                match name {
-                    name if name == config.token_a_amount_arg => {
-                        if let Some(value) = arg.get("Value") {
-                            if let Some(big_int) =
-                                value.get("bigInteger").and_then(|bi| bi.as_str())
-                            {
-                                token_a_amount = Some(big_int.to_string());
-                            }
-                        }
-                    }
-                    name if name == config.token_b_amount_arg => {
-                        if let Some(value) = arg.get("Value") {
-                            if let Some(big_int) =
-                                value.get("bigInteger").and_then(|bi| bi.as_str())
-                            {
-                                token_b_amount = Some(big_int.to_string());
-                            }
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
-    }
+    let token_a_amount = arguments
+        .token_a_amount
+        .as_deref()
+        .and_then(|name| argument_value(program, name));
+    let token_b_amount = arguments
+        .token_b_amount
+        .as_deref()
+        .and_then(|name| argument_value(program, name));
 
     (token_a_amount, token_b_amount)
 }
 
+// Reads a named instruction argument as a string, whatever its ABI value type
+pub fn argument_value(program: &Value, arg_name: &str) -> Option<String> {
+    let arguments = program.get("Arguments")?.as_array()?;
+    let value = arguments
+        .iter()
+        .find(|arg| arg.get("Name").and_then(|n| n.as_str()) == Some(arg_name))?
+        .get("Value")?;
+
+    ["bigInteger", "integer", "float", "string", "address"]
+        .iter()
+        .find_map(|key| match value.get(*key)? {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
+}
+
+fn extract_pool_parameters(
+    program: &Value,
+    config: &DexDefinition,
+    arguments: &DexArguments,
+    fee_tier: Option<String>,
+) -> PoolParameters {
+    let sqrt_price_x64 = arguments
+        .sqrt_price_x64
+        .as_deref()
+        .and_then(|name| argument_value(program, name));
+
+    let tick_spacing = arguments
+        .tick_spacing
+        .as_deref()
+        .and_then(|name| argument_value(program, name))
+        .and_then(|raw| raw.parse::<u16>().ok());
+
+    let bin_step = arguments
+        .bin_step
+        .as_deref()
+        .and_then(|name| argument_value(program, name))
+        .and_then(|raw| raw.parse::<u16>().ok());
+
+    let active_id = arguments
+        .active_id
+        .as_deref()
+        .and_then(|name| argument_value(program, name))
+        .and_then(|raw| raw.parse::<i32>().ok());
+
+    let fee_rate_bps = tick_spacing.and_then(|spacing| config.fee_rate_bps(spacing));
+
+    PoolParameters {
+        sqrt_price_x64,
+        // Needs both tokens' decimals; see refresh_derived_fields
+        initial_price: None,
+        tick_spacing,
+        fee_tier,
+        fee_rate_bps,
+        bin_step,
+        active_id,
+    }
+}
+
+// Converts a Q64.64 sqrt price into a human price of token B per token A
+pub fn sqrt_price_x64_to_price(sqrt_price_x64: u128, token_a_decimals: u8, token_b_decimals: u8) -> f64 {
+    let sqrt_price = sqrt_price_x64 as f64 / 2f64.powi(64);
+    sqrt_price * sqrt_price * 10f64.powi(token_a_decimals as i32 - token_b_decimals as i32)
+}
+
+// Price of the active bin in a liquidity-book pool, as token Y (B) per token X (A)
+pub fn active_bin_price(bin_step: u16, active_id: i32, token_a_decimals: u8, token_b_decimals: u8) -> f64 {
+    let base = 1.0 + bin_step as f64 / 10_000.0;
+    base.powi(active_id) * 10f64.powi(token_a_decimals as i32 - token_b_decimals as i32)
+}
+
+// Addresses picked out of an instruction's accounts by their configured names
+#[derive(Debug, Default)]
+struct PoolAccounts {
+    token_a_mint: Option<String>,
+    token_b_mint: Option<String>,
+    pool: Option<String>,
+    fee_tier: Option<String>,
+    vaults: Vec<String>,
+    lp_mint: Option<String>,
+    creator_lp_account: Option<String>,
+}
+
 fn extract_addresses_from_accounts(
     accounts: &[Value],
     program: &Value,
-    config: &DexConfig,
-) -> (HashMap<String, String>, Option<String>) {
-    let mut token_addresses: HashMap<String, String> = HashMap::new();
-    let mut pool_address = None;
+    account_map: &DexAccounts,
+) -> PoolAccounts {
+    let mut pool_accounts = PoolAccounts::default();
 
     let account_names = program
         .get("AccountNames")
@@ -525,24 +746,54 @@ fn extract_addresses_from_accounts(
 
             // Extract addresses based on account names
             match account_name {
-                name if name == config.token_a_account => {
-                    token_addresses.insert(config.token_a_key.clone(), address.to_string());
+                name if name == account_map.token_a_mint => {
+                    pool_accounts.token_a_mint = Some(address.to_string());
                     println!("🪙 Found token A mint: {}", address);
                 }
-                name if name == config.token_b_account => {
-                    token_addresses.insert(config.token_b_key.clone(), address.to_string());
+                name if name == account_map.token_b_mint => {
+                    pool_accounts.token_b_mint = Some(address.to_string());
                     println!("🪙 Found token B mint: {}", address);
                 }
-                name if name == config.pool_account => {
-                    pool_address = Some(address.to_string());
+                name if name == account_map.pool => {
+                    pool_accounts.pool = Some(address.to_string());
                     println!("🏊 Found pool address: {}", address);
                 }
+                name if account_map.fee_tier.as_deref() == Some(name) => {
+                    pool_accounts.fee_tier = Some(address.to_string());
+                    println!("🎚️  Found fee tier: {}", address);
+                }
+                name if account_map.lp_mint.as_deref() == Some(name) => {
+                    pool_accounts.lp_mint = Some(address.to_string());
+                    pool_accounts.vaults.push(address.to_string());
+                    println!("🎟️  Found LP mint: {}", address);
+                }
+                name if account_map.creator_lp_account.as_deref() == Some(name) => {
+                    pool_accounts.creator_lp_account = Some(address.to_string());
+                }
+                name if account_map.vaults.iter().any(|vault| vault == name) => {
+                    pool_accounts.vaults.push(address.to_string());
+                }
                 _ => {}
             }
         }
     }
 
-    (token_addresses, pool_address)
+    pool_accounts
+}
+
+// Address of the account listed under `account_name` in Program.AccountNames
+pub fn account_by_name(accounts: &[Value], program: &Value, account_name: &str) -> Option<String> {
+    let index = program
+        .get("AccountNames")?
+        .as_array()?
+        .iter()
+        .position(|name| name.as_str() == Some(account_name))?;
+
+    accounts
+        .get(index)?
+        .get("Address")?
+        .as_str()
+        .map(|address| address.to_string())
 }
 
 fn should_skip_address(address: &str) -> bool {
@@ -551,19 +802,69 @@ fn should_skip_address(address: &str) -> bool {
         || address == "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA"
 }
 
-fn determine_sol_amount(
-    token_a_address: &str,
-    token_b_address: &str,
-    token_a_amount: &Option<String>,
-    token_b_amount: &Option<String>,
-) -> Option<String> {
-    let sol_address = "So11111111111111111111111111111111111111112";
-
-    if token_a_address == sol_address {
-        token_a_amount.clone()
-    } else if token_b_address == sol_address {
-        token_b_amount.clone()
-    } else {
-        None
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const Q64: f64 = 18_446_744_073_709_551_616.0;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= expected.abs() * 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn sqrt_price_of_one_with_equal_decimals() {
+        assert_close(sqrt_price_x64_to_price(1u128 << 64, 9, 9), 1.0);
+    }
+
+    #[test]
+    fn sqrt_price_applies_decimal_difference() {
+        // Raw price 4 (sqrt 2): A has 9 decimals, B has 6
+        let sqrt_price_x64 = (2.0 * Q64) as u128;
+        assert_close(sqrt_price_x64_to_price(sqrt_price_x64, 9, 6), 4_000.0);
+        assert_close(sqrt_price_x64_to_price(sqrt_price_x64, 6, 9), 0.004);
+    }
+
+    #[test]
+    fn sqrt_price_is_reciprocal_when_tokens_swap() {
+        // The same market with the mints in the other order stores 1 / sqrt_price
+        let forward = sqrt_price_x64_to_price((2.0 * Q64) as u128, 6, 9);
+        let reversed = sqrt_price_x64_to_price((0.5 * Q64) as u128, 9, 6);
+        assert_close(forward * reversed, 1.0);
+        assert_close(reversed, 250.0);
+    }
+
+    #[test]
+    fn sqrt_price_of_small_prices_keeps_precision() {
+        // A memecoin at 1e-8 SOL: sqrt 1e-4
+        let sqrt_price_x64 = (1e-4 * Q64) as u128;
+        assert_close(sqrt_price_x64_to_price(sqrt_price_x64, 9, 9), 1e-8);
+    }
+
+    #[test]
+    fn active_bin_zero_is_parity() {
+        assert_close(active_bin_price(25, 0, 6, 6), 1.0);
+    }
+
+    #[test]
+    fn active_bin_price_at_positive_and_negative_ids() {
+        // 25 bps bins: each bin is 0.25% above the previous one
+        let up = active_bin_price(25, 100, 9, 9);
+        let down = active_bin_price(25, -100, 9, 9);
+        assert_close(up, 1.0025f64.powi(100));
+        assert_close(down, 1.0 / 1.0025f64.powi(100));
+        assert_close(up * down, 1.0);
+    }
+
+    #[test]
+    fn active_bin_price_applies_decimal_difference() {
+        // X with 9 decimals, Y with 6: one bin below parity
+        assert_close(active_bin_price(100, -1, 9, 6), 1_000.0 / 1.01);
+        assert_close(active_bin_price(100, 1, 6, 9), 1.01 / 1_000.0);
     }
 }
@@ -0,0 +1,419 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::Value;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::onchain::parse_pubkey;
+
+// Pyth SOL/USD price feed account (shard 0) on mainnet, kept current by the
+// Pyth receiver program
+const DEFAULT_PYTH_SOL_USD_ACCOUNT: &str = "7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE";
+const DEFAULT_PRICE_URL: &str = "https://api.coingecko.com/api/v3/simple/price?ids=solana&vs_currencies=usd";
+const DEFAULT_PRICE_POINTER: &str = "/solana/usd";
+const DEFAULT_MOCK_SOL_USD: f64 = 150.0;
+const DEFAULT_PRICE_MAX_AGE: Duration = Duration::from_secs(30);
+const DEFAULT_PYTH_MAX_STALENESS: Duration = Duration::from_secs(60);
+// Retry delay after a failed lookup, doubled on every further failure
+const MIN_RETRY_DELAY: Duration = Duration::from_secs(5);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+
+// PriceUpdateV2 account written by the Pyth receiver program: an 8-byte
+// Anchor discriminator, the write authority, the verification level (one byte
+// for Full, two for Partial) and then the price message. Offsets into the
+// message are for its feed id, price, exponent and publish time.
+const PRICE_UPDATE_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+const PRICE_UPDATE_VERIFICATION_OFFSET: usize = 40;
+const PRICE_UPDATE_VERIFIED_FULL: u8 = 1;
+const PRICE_UPDATE_MESSAGE_OFFSET: usize = 41;
+const PRICE_MESSAGE_PRICE_OFFSET: usize = 32;
+const PRICE_MESSAGE_EXPO_OFFSET: usize = 48;
+const PRICE_MESSAGE_TIMESTAMP_OFFSET: usize = 52;
+const PYTH_SOL_USD_FEED_ID: &str = "ef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
+
+// Legacy (v2) Pyth push-oracle price account, as written by the Pyth oracle
+// program on Solana mainnet. Only the fields read here are listed: exponent,
+// the aggregate's publish time, and the aggregate price and status. Many of
+// these accounts are no longer updated, which the staleness check catches.
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_EXPO_OFFSET: usize = 20;
+const PYTH_TIMESTAMP_OFFSET: usize = 96;
+const PYTH_AGG_PRICE_OFFSET: usize = 208;
+const PYTH_AGG_STATUS_OFFSET: usize = 224;
+const PYTH_STATUS_TRADING: u32 = 1;
+
+// A source of the SOL/USD rate
+#[async_trait]
+pub trait PriceFeed: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn sol_usd(&self) -> Result<f64>;
+}
+
+// Reads SOL/USD straight from a Pyth price update (or legacy price) account
+// over RPC. A price nobody updates any more still decodes, so one published
+// more than `max_staleness` ago is rejected.
+pub struct PythFeed {
+    rpc: RpcClient,
+    account: Pubkey,
+    max_staleness: Duration,
+}
+
+impl PythFeed {
+    pub fn new(url: String, account: &str, max_staleness: Duration) -> Result<Self> {
+        Ok(Self {
+            rpc: RpcClient::new_with_commitment(url, CommitmentConfig::confirmed()),
+            account: parse_pubkey(account)?,
+            max_staleness,
+        })
+    }
+}
+
+#[async_trait]
+impl PriceFeed for PythFeed {
+    fn name(&self) -> &str {
+        "Pyth"
+    }
+
+    async fn sol_usd(&self) -> Result<f64> {
+        let account = self
+            .rpc
+            .get_account(&self.account)
+            .await
+            .with_context(|| format!("Failed to fetch Pyth price account {}", self.account))?;
+
+        decode_pyth_price(&account.data, chrono::Utc::now().timestamp(), self.max_staleness)
+            .with_context(|| format!("Pyth price account {}", self.account))
+    }
+}
+
+// SOL/USD from the raw bytes of a price account, at unix time `now`
+fn decode_pyth_price(data: &[u8], now: i64, max_staleness: Duration) -> Result<f64> {
+    let (price, expo, published_at) = if data.starts_with(&PRICE_UPDATE_DISCRIMINATOR) {
+        decode_price_update(data)?
+    } else if read_bytes(data, 0).map(u32::from_le_bytes).ok() == Some(PYTH_MAGIC) {
+        decode_legacy_price(data)?
+    } else {
+        bail!("Not a Pyth price account");
+    };
+
+    let age_secs = now.saturating_sub(published_at);
+    if age_secs > max_staleness.as_secs() as i64 {
+        bail!("Price was last published {}s ago", age_secs);
+    }
+
+    Ok(price as f64 * 10f64.powi(expo))
+}
+
+// (price, exponent, publish time) of a fully verified SOL/USD price update
+fn decode_price_update(data: &[u8]) -> Result<(i64, i32, i64)> {
+    if data.get(PRICE_UPDATE_VERIFICATION_OFFSET) != Some(&PRICE_UPDATE_VERIFIED_FULL) {
+        bail!("Price update is not fully verified");
+    }
+    let message = PRICE_UPDATE_MESSAGE_OFFSET;
+    let feed_id: [u8; 32] = read_bytes(data, message)?;
+    let feed_id: String = feed_id.iter().map(|byte| format!("{:02x}", byte)).collect();
+    if feed_id != PYTH_SOL_USD_FEED_ID {
+        bail!("Price update is for feed {}, not SOL/USD", feed_id);
+    }
+
+    Ok((
+        i64::from_le_bytes(read_bytes(data, message + PRICE_MESSAGE_PRICE_OFFSET)?),
+        i32::from_le_bytes(read_bytes(data, message + PRICE_MESSAGE_EXPO_OFFSET)?),
+        i64::from_le_bytes(read_bytes(data, message + PRICE_MESSAGE_TIMESTAMP_OFFSET)?),
+    ))
+}
+
+// (price, exponent, publish time) of a trading legacy price account
+fn decode_legacy_price(data: &[u8]) -> Result<(i64, i32, i64)> {
+    if read_bytes(data, PYTH_AGG_STATUS_OFFSET).map(u32::from_le_bytes)? != PYTH_STATUS_TRADING {
+        bail!("Price is not trading");
+    }
+
+    Ok((
+        i64::from_le_bytes(read_bytes(data, PYTH_AGG_PRICE_OFFSET)?),
+        i32::from_le_bytes(read_bytes(data, PYTH_EXPO_OFFSET)?),
+        i64::from_le_bytes(read_bytes(data, PYTH_TIMESTAMP_OFFSET)?),
+    ))
+}
+
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .map(|bytes| bytes.try_into().expect("N bytes"))
+        .context("Truncated price account")
+}
+
+// Any JSON endpoint; the price is picked out with a JSON pointer
+pub struct HttpPriceFeed {
+    client: Client,
+    url: String,
+    pointer: String,
+}
+
+impl HttpPriceFeed {
+    pub fn new(url: String, pointer: String) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))?;
+
+        Ok(Self { client, url, pointer })
+    }
+}
+
+#[async_trait]
+impl PriceFeed for HttpPriceFeed {
+    fn name(&self) -> &str {
+        "HTTP"
+    }
+
+    async fn sol_usd(&self) -> Result<f64> {
+        let json: Value = self
+            .client
+            .get(&self.url)
+            .header("accept", "application/json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let price = json.pointer(&self.pointer).context("Price missing from price endpoint response")?;
+        price
+            .as_f64()
+            .or_else(|| price.as_str().and_then(|text| text.parse().ok()))
+            .ok_or_else(|| anyhow!("Price at {} is not a number: {}", self.pointer, price))
+    }
+}
+
+// Fixed price for local runs without network access
+pub struct MockPriceFeed {
+    sol_usd: f64,
+}
+
+#[async_trait]
+impl PriceFeed for MockPriceFeed {
+    fn name(&self) -> &str {
+        "Mock"
+    }
+
+    async fn sol_usd(&self) -> Result<f64> {
+        Ok(self.sol_usd)
+    }
+}
+
+// Keeps the feed's last price, refreshed every PRICE_MAX_AGE_SECS by a
+// background task, so pricing a pool never waits on the network. Failed
+// lookups are retried with backoff while the last price, however old, stays
+// in use; there is no price until the first lookup succeeds.
+#[derive(Clone)]
+pub struct PriceOracle {
+    feed: Arc<dyn PriceFeed>,
+    max_age: Duration,
+    last: Arc<Mutex<Option<f64>>>,
+}
+
+impl PriceOracle {
+    // PRICE_FEED picks the source: pyth (default when HTTPS_ENDPOINT is set),
+    // http, mock or none. Returns None when USD valuation is turned off.
+    pub fn from_env() -> Result<Option<Self>> {
+        let env = |name: &str| std::env::var(name).ok();
+        let rpc_url = env("HTTPS_ENDPOINT");
+        let kind = env("PRICE_FEED")
+            .map(|kind| kind.to_lowercase())
+            .unwrap_or_else(|| if rpc_url.is_some() { "pyth" } else { "none" }.to_string());
+
+        let feed: Arc<dyn PriceFeed> = match kind.as_str() {
+            "pyth" => {
+                let url = rpc_url.context("PRICE_FEED=pyth needs HTTPS_ENDPOINT")?;
+                let account = env("PYTH_SOL_USD_ACCOUNT").unwrap_or_else(|| DEFAULT_PYTH_SOL_USD_ACCOUNT.to_string());
+                let max_staleness = positive_secs("PYTH_MAX_STALENESS_SECS")?.unwrap_or(DEFAULT_PYTH_MAX_STALENESS);
+                Arc::new(PythFeed::new(url, &account, max_staleness)?)
+            }
+            "http" => Arc::new(HttpPriceFeed::new(
+                env("PRICE_FEED_URL").unwrap_or_else(|| DEFAULT_PRICE_URL.to_string()),
+                env("PRICE_FEED_POINTER").unwrap_or_else(|| DEFAULT_PRICE_POINTER.to_string()),
+            )?),
+            "mock" => {
+                let sol_usd = match env("MOCK_SOL_USD") {
+                    Some(value) => value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|price| price.is_finite() && *price > 0.0)
+                        .with_context(|| format!("Invalid MOCK_SOL_USD '{}'", value))?,
+                    None => DEFAULT_MOCK_SOL_USD,
+                };
+                Arc::new(MockPriceFeed { sol_usd })
+            }
+            "none" => return Ok(None),
+            _ => bail!("Unknown PRICE_FEED '{}'; expected pyth, http, mock or none", kind),
+        };
+
+        // Zero would make the refresh task spin
+        let max_age = positive_secs("PRICE_MAX_AGE_SECS")?.unwrap_or(DEFAULT_PRICE_MAX_AGE);
+
+        println!("💵 SOL/USD price feed: {} (refreshed every {}s)", feed.name(), max_age.as_secs());
+        Ok(Some(Self::new(feed, max_age)))
+    }
+
+    pub fn new(feed: Arc<dyn PriceFeed>, max_age: Duration) -> Self {
+        Self {
+            feed,
+            max_age,
+            last: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn sol_usd(&self) -> Option<f64> {
+        *self.last.lock().unwrap()
+    }
+
+    // Runs for the lifetime of the server
+    pub fn spawn_refresh(&self) {
+        let oracle = self.clone();
+        tokio::spawn(async move {
+            let mut retry_delay = MIN_RETRY_DELAY;
+            loop {
+                let delay = match oracle.refresh().await {
+                    Ok(()) => {
+                        retry_delay = MIN_RETRY_DELAY;
+                        oracle.max_age
+                    }
+                    Err(e) => {
+                        // Until the first price arrives every pool goes unvalued in USD
+                        if oracle.sol_usd().is_none() {
+                            eprintln!(
+                                "❌ {} price feed has no SOL/USD price yet (check PRICE_FEED / PYTH_SOL_USD_ACCOUNT), retrying in {}s: {:#}",
+                                oracle.feed.name(),
+                                retry_delay.as_secs(),
+                                e
+                            );
+                        } else {
+                            eprintln!(
+                                "⚠️  {} price feed failed, retrying in {}s: {:#}",
+                                oracle.feed.name(),
+                                retry_delay.as_secs(),
+                                e
+                            );
+                        }
+                        let delay = retry_delay;
+                        retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+                        delay
+                    }
+                };
+                tokio::time::sleep(delay).await;
+            }
+        });
+    }
+
+    async fn refresh(&self) -> Result<()> {
+        let price = self.feed.sol_usd().await?;
+        if !price.is_finite() || price <= 0.0 {
+            bail!("Invalid SOL/USD price {}", price);
+        }
+        *self.last.lock().unwrap() = Some(price);
+        Ok(())
+    }
+}
+
+// A whole number of seconds from the environment; zero is rejected
+fn positive_secs(name: &str) -> Result<Option<Duration>> {
+    std::env::var(name)
+        .ok()
+        .map(|value| {
+            value
+                .parse::<u64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+                .with_context(|| format!("Invalid {} '{}': expected a positive number of seconds", name, value))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_800_000_000;
+    const MAX_STALENESS: Duration = Duration::from_secs(60);
+
+    // $150.25 at expo -8, trading, published at `published_at`
+    fn price_account(published_at: i64) -> Vec<u8> {
+        let mut data = vec![0u8; 240];
+        data[0..4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[PYTH_EXPO_OFFSET..PYTH_EXPO_OFFSET + 4].copy_from_slice(&(-8i32).to_le_bytes());
+        data[PYTH_TIMESTAMP_OFFSET..PYTH_TIMESTAMP_OFFSET + 8].copy_from_slice(&published_at.to_le_bytes());
+        data[PYTH_AGG_PRICE_OFFSET..PYTH_AGG_PRICE_OFFSET + 8].copy_from_slice(&15_025_000_000i64.to_le_bytes());
+        data[PYTH_AGG_STATUS_OFFSET..PYTH_AGG_STATUS_OFFSET + 4].copy_from_slice(&PYTH_STATUS_TRADING.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn decodes_a_fresh_trading_price() {
+        let price = decode_pyth_price(&price_account(NOW - 5), NOW, MAX_STALENESS).expect("fresh price");
+        assert!((price - 150.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_a_stale_price() {
+        assert!(decode_pyth_price(&price_account(NOW - 60), NOW, MAX_STALENESS).is_ok());
+        assert!(decode_pyth_price(&price_account(NOW - 61), NOW, MAX_STALENESS).is_err());
+    }
+
+    #[test]
+    fn rejects_other_accounts_and_halted_prices() {
+        let mut halted = price_account(NOW);
+        halted[PYTH_AGG_STATUS_OFFSET] = 0;
+        assert!(decode_pyth_price(&halted, NOW, MAX_STALENESS).is_err());
+
+        let mut other = price_account(NOW);
+        other[0] = 0;
+        assert!(decode_pyth_price(&other, NOW, MAX_STALENESS).is_err());
+
+        assert!(decode_pyth_price(&price_account(NOW)[..100], NOW, MAX_STALENESS).is_err());
+    }
+
+    // $150.25 at expo -8 from a fully verified SOL/USD update published at `published_at`
+    fn price_update(published_at: i64) -> Vec<u8> {
+        let message = PRICE_UPDATE_MESSAGE_OFFSET;
+        let mut data = vec![0u8; 134];
+        data[0..8].copy_from_slice(&PRICE_UPDATE_DISCRIMINATOR);
+        data[PRICE_UPDATE_VERIFICATION_OFFSET] = PRICE_UPDATE_VERIFIED_FULL;
+        for (i, byte) in data[message..message + 32].iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&PYTH_SOL_USD_FEED_ID[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        let price = message + PRICE_MESSAGE_PRICE_OFFSET;
+        data[price..price + 8].copy_from_slice(&15_025_000_000i64.to_le_bytes());
+        let expo = message + PRICE_MESSAGE_EXPO_OFFSET;
+        data[expo..expo + 4].copy_from_slice(&(-8i32).to_le_bytes());
+        let timestamp = message + PRICE_MESSAGE_TIMESTAMP_OFFSET;
+        data[timestamp..timestamp + 8].copy_from_slice(&published_at.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn decodes_a_fresh_price_update() {
+        let price = decode_pyth_price(&price_update(NOW - 5), NOW, MAX_STALENESS).expect("fresh price");
+        assert!((price - 150.25).abs() < 1e-9);
+        assert!(decode_pyth_price(&price_update(NOW - 61), NOW, MAX_STALENESS).is_err());
+    }
+
+    #[test]
+    fn rejects_unverified_or_other_price_updates() {
+        let mut partial = price_update(NOW);
+        partial[PRICE_UPDATE_VERIFICATION_OFFSET] = 0;
+        assert!(decode_pyth_price(&partial, NOW, MAX_STALENESS).is_err());
+
+        let mut other_feed = price_update(NOW);
+        other_feed[PRICE_UPDATE_MESSAGE_OFFSET] ^= 0xff;
+        assert!(decode_pyth_price(&other_feed, NOW, MAX_STALENESS).is_err());
+
+        assert!(decode_pyth_price(&price_update(NOW)[..100], NOW, MAX_STALENESS).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use async_tungstenite::{
+    tokio::{connect_async, ConnectStream},
+    tungstenite::{
+        client::IntoClientRequest,
+        http::{header, HeaderValue},
+        Message,
+    },
+    WebSocketStream,
+};
+use futures::{
+    stream::{SplitSink, SplitStream},
+    SinkExt, StreamExt,
+};
+use serde_json::{json, Value};
+
+// Bitquery sends a keep-alive about every 10s. After this much silence we probe
+// with a WebSocket ping, and a socket that stays silent for the idle timeout
+// (BITQUERY_IDLE_TIMEOUT_SECS, a few keep-alive intervals) is treated as dead.
+const PING_AFTER: Duration = Duration::from_secs(15);
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(45);
+
+// GraphQL-over-WebSocket dialects Bitquery accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WsProtocol {
+    // graphql-transport-ws (graphql-ws library): subscribe / next / ping / pong
+    GraphqlTransportWs,
+    // Legacy subscriptions-transport-ws, negotiated as "graphql-ws": start / data / ka
+    GraphqlWs,
+}
+
+impl WsProtocol {
+    // BITQUERY_WS_PROTOCOL=graphql-ws switches to the legacy dialect
+    pub fn from_env() -> Self {
+        match std::env::var("BITQUERY_WS_PROTOCOL").as_deref() {
+            Ok("graphql-ws") => WsProtocol::GraphqlWs,
+            Ok("graphql-transport-ws") | Err(_) => WsProtocol::GraphqlTransportWs,
+            Ok(other) => {
+                eprintln!(
+                    "⚠️  Unknown BITQUERY_WS_PROTOCOL '{}', using graphql-transport-ws",
+                    other
+                );
+                WsProtocol::GraphqlTransportWs
+            }
+        }
+    }
+
+    fn subprotocol(&self) -> &'static str {
+        match self {
+            WsProtocol::GraphqlTransportWs => "graphql-transport-ws",
+            WsProtocol::GraphqlWs => "graphql-ws",
+        }
+    }
+
+    fn subscribe_type(&self) -> &'static str {
+        match self {
+            WsProtocol::GraphqlTransportWs => "subscribe",
+            WsProtocol::GraphqlWs => "start",
+        }
+    }
+}
+
+// What happened to one of our subscription operations
+#[derive(Debug)]
+pub enum OperationEvent {
+    Next { id: String, payload: Value },
+    Error { id: String, payload: Value },
+    Complete { id: String },
+}
+
+type WsStream = WebSocketStream<ConnectStream>;
+
+pub struct GraphqlWsClient {
+    protocol: WsProtocol,
+    write: SplitSink<WsStream, Message>,
+    read: SplitStream<WsStream>,
+    active_operations: HashSet<String>,
+    idle_timeout: Duration,
+}
+
+impl GraphqlWsClient {
+    // Opens the socket and completes the connection_init / connection_ack handshake
+    pub async fn connect(url: &str, oauth_token: &str, protocol: WsProtocol) -> Result<Self> {
+        let mut request = url.into_client_request()?;
+
+        request.headers_mut().insert(
+            header::SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_str(protocol.subprotocol())?,
+        );
+        request.headers_mut().insert(
+            "Authorization",
+            HeaderValue::from_str(&format!("Bearer {}", oauth_token))?,
+        );
+
+        let (ws_stream, _) = connect_async(request).await?;
+        let (write, read) = ws_stream.split();
+
+        let mut client = Self {
+            protocol,
+            write,
+            read,
+            active_operations: HashSet::new(),
+            idle_timeout: idle_timeout_from_env(),
+        };
+
+        client.send_json(json!({ "type": "connection_init" })).await?;
+
+        while let Some(frame) = client.read_frame().await? {
+            match frame["type"].as_str() {
+                Some("connection_ack") => return Ok(client),
+                Some("connection_error") => {
+                    return Err(anyhow!("Connection rejected: {}", frame["payload"]));
+                }
+                Some("ping") => client.send_pong(&frame).await?,
+                Some("ka") | Some("pong") => {}
+                _ => println!("📥 Unexpected message before ack: {}", frame),
+            }
+        }
+
+        Err(anyhow!("Socket closed before connection_ack"))
+    }
+
+    pub async fn subscribe(&mut self, id: &str, query: &str) -> Result<()> {
+        self.send_json(json!({
+            "id": id,
+            "type": self.protocol.subscribe_type(),
+            "payload": {
+                "query": query
+            }
+        }))
+        .await?;
+        self.active_operations.insert(id.to_string());
+        Ok(())
+    }
+
+    pub fn has_active_operations(&self) -> bool {
+        !self.active_operations.is_empty()
+    }
+
+    // Waits for the next operation message, answering pings and skipping
+    // keep-alives along the way. Returns None once the socket closes.
+    pub async fn next_event(&mut self) -> Result<Option<OperationEvent>> {
+        while let Some(frame) = self.read_frame().await? {
+            let id = frame["id"].as_str().unwrap_or_default().to_string();
+
+            match frame["type"].as_str() {
+                Some("next") | Some("data") => {
+                    let payload = frame.get("payload").cloned().unwrap_or(Value::Null);
+                    return Ok(Some(OperationEvent::Next { id, payload }));
+                }
+                Some("error") => {
+                    self.active_operations.remove(&id);
+                    let payload = frame.get("payload").cloned().unwrap_or(Value::Null);
+                    return Ok(Some(OperationEvent::Error { id, payload }));
+                }
+                Some("complete") => {
+                    if !self.active_operations.remove(&id) {
+                        println!("🔍 Complete for unknown operation '{}'", id);
+                    }
+                    return Ok(Some(OperationEvent::Complete { id }));
+                }
+                Some("ping") => self.send_pong(&frame).await?,
+                Some("pong") | Some("ka") => {}
+                Some("connection_error") => {
+                    return Err(anyhow!("Connection error: {}", frame["payload"]));
+                }
+                _ => println!("🔍 Ignoring unknown protocol message: {}", frame),
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Next text frame decoded as JSON; transport-level frames are skipped but
+    // count as signs of life. Errors once the socket has been silent for the
+    // idle timeout, which is how a half-open connection shows up.
+    async fn read_frame(&mut self) -> Result<Option<Value>> {
+        let mut last_heard = Instant::now();
+        loop {
+            let silent_for = last_heard.elapsed();
+            if silent_for >= self.idle_timeout {
+                return Err(anyhow!("No message from server in {:?}", silent_for));
+            }
+            let wait = PING_AFTER.min(self.idle_timeout - silent_for);
+            let Ok(msg) = tokio::time::timeout(wait, self.read.next()).await else {
+                self.write.send(Message::Ping(Vec::new())).await?;
+                continue;
+            };
+            let Some(msg) = msg else {
+                return Ok(None);
+            };
+            last_heard = Instant::now();
+
+            match msg? {
+                Message::Text(text) => {
+                    let frame = serde_json::from_str(&text)
+                        .map_err(|e| anyhow!("Invalid protocol frame '{}': {}", text, e))?;
+                    return Ok(Some(frame));
+                }
+                Message::Close(frame) => {
+                    println!("🔌 Server closed the socket: {:?}", frame);
+                    return Ok(None);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn send_pong(&mut self, ping: &Value) -> Result<()> {
+        let mut pong = json!({ "type": "pong" });
+        if let Some(payload) = ping.get("payload") {
+            pong["payload"] = payload.clone();
+        }
+        self.send_json(pong).await
+    }
+
+    async fn send_json(&mut self, message: Value) -> Result<()> {
+        self.write.send(Message::Text(message.to_string())).await?;
+        Ok(())
+    }
+}
+
+fn idle_timeout_from_env() -> Duration {
+    match std::env::var("BITQUERY_IDLE_TIMEOUT_SECS") {
+        Ok(value) => match value.parse::<u64>() {
+            Ok(secs) if secs > 0 => Duration::from_secs(secs),
+            _ => {
+                eprintln!("⚠️  Invalid BITQUERY_IDLE_TIMEOUT_SECS '{}', using {:?}", value, DEFAULT_IDLE_TIMEOUT);
+                DEFAULT_IDLE_TIMEOUT
+            }
+        },
+        Err(_) => DEFAULT_IDLE_TIMEOUT,
+    }
+}
@@ -0,0 +1,163 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+// Attempts per request for 429s, 5xx and network errors
+const MAX_ATTEMPTS: u32 = 4;
+const RETRY_BASE: Duration = Duration::from_secs(1);
+// Longest Retry-After we are willing to sit out before giving up on a request
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+
+// Token bucket: `burst` requests may go out back to back, after which requests
+// are spaced to `per_minute`. Callers wait for a token rather than failing.
+pub struct RateLimiter {
+    name: String,
+    capacity: f64,
+    refill_per_sec: f64,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(name: &str, per_minute: u32, burst: u32) -> Self {
+        let capacity = burst.max(1) as f64;
+        Self {
+            name: name.to_string(),
+            capacity,
+            refill_per_sec: per_minute.max(1) as f64 / 60.0,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    // Reads <PREFIX>_REQUESTS_PER_MINUTE and <PREFIX>_BURST, falling back to the defaults
+    pub fn from_env(name: &str, prefix: &str, default_per_minute: u32, default_burst: u32) -> Result<Self> {
+        let per_minute = env_u32(&format!("{}_REQUESTS_PER_MINUTE", prefix), default_per_minute)?;
+        let burst = env_u32(&format!("{}_BURST", prefix), default_burst)?;
+
+        println!(
+            "🚦 {} rate limit: {} requests/minute, burst {}",
+            name, per_minute, burst
+        );
+
+        Ok(Self::new(name, per_minute, burst))
+    }
+
+    pub async fn acquire(&self) {
+        // The lock is held while waiting so callers are served in order
+        let mut state = self.state.lock().await;
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        state.refilled_at = now;
+
+        if state.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - state.tokens) / self.refill_per_sec);
+            println!("🚦 {} rate limit reached, waiting {:?}", self.name, wait);
+            tokio::time::sleep(wait).await;
+            state.tokens = 1.0;
+            state.refilled_at = Instant::now();
+        }
+
+        state.tokens -= 1.0;
+    }
+}
+
+// Sends the request built by `build` once the limiter allows it, retrying
+// rate-limited (429), server-side (5xx) and network failures with exponential
+// backoff, or after Retry-After when the server sends one. `label` names the
+// request in log lines.
+pub async fn send_with_retries<F>(limiter: &RateLimiter, label: &str, build: F) -> Result<Response>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let mut attempt = 1;
+
+    loop {
+        limiter.acquire().await;
+
+        let (error, retry_in) = match build().send().await {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) if is_retryable(response.status()) => {
+                let status = response.status();
+                let retry_after = retry_after(&response);
+                let error_body = response.text().await.unwrap_or_default();
+                (
+                    anyhow!("API request failed: {} - {}", status, error_body),
+                    retry_after.unwrap_or_else(|| retry_backoff(attempt)),
+                )
+            }
+            Ok(response) => {
+                let status = response.status();
+                let error_body = response.text().await.unwrap_or_default();
+                return Err(anyhow!("API request failed: {} - {}", status, error_body));
+            }
+            Err(e) => (
+                anyhow!("Failed to send request: {}", e),
+                retry_backoff(attempt),
+            ),
+        };
+
+        if attempt >= MAX_ATTEMPTS {
+            return Err(error.context(format!("Gave up after {} attempts", attempt)));
+        }
+        if retry_in > MAX_RETRY_WAIT {
+            return Err(error.context(format!("Server asked to retry in {:?}", retry_in)));
+        }
+
+        println!(
+            "⏳ {} failed ({}), retry {}/{} in {:?}",
+            label,
+            error,
+            attempt,
+            MAX_ATTEMPTS - 1,
+            retry_in
+        );
+        tokio::time::sleep(retry_in).await;
+        attempt += 1;
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+// Retry-After as either delta-seconds or an HTTP date
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let retry_at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = retry_at.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+// 1s, 2s, 4s... with equal jitter so parallel lookups don't retry in lockstep
+fn retry_backoff(attempt: u32) -> Duration {
+    let delay = RETRY_BASE * 2u32.pow(attempt - 1);
+    delay / 2 + delay.mul_f64(rand::random::<f64>() / 2.0)
+}
+
+fn env_u32(name: &str, default: u32) -> Result<u32> {
+    match std::env::var(name) {
+        Ok(value) => value
+            .parse::<u32>()
+            .ok()
+            .filter(|parsed| *parsed > 0)
+            .with_context(|| format!("Invalid {} '{}': expected a positive integer", name, value)),
+        Err(_) => Ok(default),
+    }
+}
@@ -0,0 +1,360 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use futures::future::join_all;
+
+use crate::cache::AnalysisCache;
+use crate::onchain::{
+    apply_holders, apply_onchain_mint, onchain_token_data, HolderConcentration, MintInspector, OnChainMint,
+};
+use crate::parsing::PoolCreationEvent;
+use crate::rugcheck::RugCheckClient;
+use crate::rules::RuleSet;
+use crate::solsniffer::{print_analysis, ProviderScore, RiskLevel, SolSnifferClient, TokenData};
+
+const DEFAULT_RISK_PROVIDERS: &str = "solsniffer";
+// Share of the responding providers' weight that must call a token a honeypot
+const DEFAULT_HONEYPOT_THRESHOLD: f64 = 0.5;
+
+// A backend that can produce a risk assessment for a mint
+#[async_trait]
+pub trait TokenRiskProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn assess(&self, token_address: &str) -> Result<TokenData>;
+}
+
+pub struct WeightedProvider {
+    pub provider: Arc<dyn TokenRiskProvider>,
+    pub weight: f64,
+}
+
+// Asks every provider at once and merges the answers, so one vendor being
+// down or wrong doesn't decide a token's risk on its own. Providers that fail
+// are left out and the remaining weights renormalised; the lookup only fails
+// when none of them answer.
+pub struct AggregateProvider {
+    name: String,
+    providers: Vec<WeightedProvider>,
+    honeypot_threshold: f64,
+}
+
+impl AggregateProvider {
+    pub fn new(providers: Vec<WeightedProvider>, honeypot_threshold: f64) -> Self {
+        let name = providers
+            .iter()
+            .map(|weighted| weighted.provider.name())
+            .collect::<Vec<_>>()
+            .join("+");
+
+        Self {
+            name,
+            providers,
+            honeypot_threshold,
+        }
+    }
+}
+
+#[async_trait]
+impl TokenRiskProvider for AggregateProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn assess(&self, token_address: &str) -> Result<TokenData> {
+        let results = join_all(
+            self.providers
+                .iter()
+                .map(|weighted| weighted.provider.assess(token_address)),
+        )
+        .await;
+
+        let mut assessments = Vec::new();
+        let mut failures = Vec::new();
+        for (weighted, result) in self.providers.iter().zip(results) {
+            match result {
+                Ok(token_data) => assessments.push((weighted, token_data)),
+                Err(e) => {
+                    eprintln!(
+                        "⚠️  {} unavailable for {}: {:#}",
+                        weighted.provider.name(),
+                        token_address,
+                        e
+                    );
+                    failures.push(format!("{}: {:#}", weighted.provider.name(), e));
+                }
+            }
+        }
+
+        if assessments.is_empty() {
+            bail!("All risk providers failed - {}", failures.join("; "));
+        }
+
+        let total_weight: f64 = assessments.iter().map(|(weighted, _)| weighted.weight).sum();
+        let weighted_share = |predicate: &dyn Fn(&TokenData) -> bool| {
+            assessments
+                .iter()
+                .filter(|(_, token_data)| predicate(token_data))
+                .map(|(weighted, _)| weighted.weight)
+                .sum::<f64>()
+                / total_weight
+        };
+
+        let score = assessments
+            .iter()
+            .map(|(weighted, token_data)| weighted.weight * token_data.score as f64)
+            .sum::<f64>()
+            / total_weight;
+        let score = score.round() as u32;
+
+        // Metadata comes from the most trusted provider that answered
+        let (_, base) = assessments
+            .iter()
+            .max_by(|(a, _), (b, _)| a.weight.total_cmp(&b.weight))
+            .expect("checked non-empty");
+        let mut merged = base.clone();
+
+        merged.score = score;
+        merged.risk_level = RiskLevel::from_score(score);
+        merged.is_honeypot = weighted_share(&|token_data| token_data.is_honeypot) >= self.honeypot_threshold;
+        // Safety flags only hold when every provider that answered agrees
+        merged.mint_disabled = assessments.iter().all(|(_, token_data)| token_data.mint_disabled);
+        merged.freeze_disabled = assessments.iter().all(|(_, token_data)| token_data.freeze_disabled);
+        merged.lp_burned = assessments.iter().all(|(_, token_data)| token_data.lp_burned);
+        merged.top_10_percentage = assessments
+            .iter()
+            .map(|(_, token_data)| token_data.top_10_percentage)
+            .fold(0.0, f64::max);
+        merged.high_risk_count = assessments
+            .iter()
+            .map(|(_, token_data)| token_data.high_risk_count)
+            .max()
+            .unwrap_or(0);
+
+        merged.audit_risks = assessments
+            .iter()
+            .flat_map(|(weighted, token_data)| {
+                token_data
+                    .audit_risks
+                    .iter()
+                    .map(move |risk| format!("[{}] {}", weighted.provider.name(), risk))
+            })
+            .chain(failures.iter().map(|failure| format!("[unavailable] {}", failure)))
+            .collect();
+
+        merged.provider_scores = assessments
+            .iter()
+            .map(|(weighted, token_data)| ProviderScore {
+                provider: weighted.provider.name().to_string(),
+                weight: weighted.weight / total_weight,
+                score: token_data.score,
+                risk_level: token_data.risk_level.clone(),
+                is_honeypot: token_data.is_honeypot,
+            })
+            .collect();
+
+        Ok(merged)
+    }
+}
+
+// RISK_PROVIDERS lists the backends as name[:weight] pairs, e.g.
+// "solsniffer:0.6,rugcheck:0.4" (default "solsniffer"); several providers are
+// combined by an AggregateProvider and RISK_HONEYPOT_THRESHOLD sets the share
+// of weight needed to flag a honeypot. Returns None when no provider is left,
+// which only happens when a missing SolSniffer key is allowed.
+pub fn provider_from_env(allow_missing_solsniffer_key: bool) -> Result<Option<Arc<dyn TokenRiskProvider>>> {
+    let spec = std::env::var("RISK_PROVIDERS").unwrap_or_else(|_| DEFAULT_RISK_PROVIDERS.to_string());
+    let honeypot_threshold = match std::env::var("RISK_HONEYPOT_THRESHOLD") {
+        Ok(value) => value
+            .parse::<f64>()
+            .ok()
+            .filter(|threshold| *threshold > 0.0 && *threshold <= 1.0)
+            .with_context(|| format!("Invalid RISK_HONEYPOT_THRESHOLD '{}': expected a number in (0, 1]", value))?,
+        Err(_) => DEFAULT_HONEYPOT_THRESHOLD,
+    };
+
+    let mut providers = Vec::new();
+    for entry in spec.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let (name, weight) = match entry.split_once(':') {
+            Some((name, weight)) => {
+                let weight = weight
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|weight| weight.is_finite() && *weight > 0.0)
+                    .with_context(|| format!("Invalid weight in RISK_PROVIDERS entry '{}'", entry))?;
+                (name.trim().to_lowercase(), weight)
+            }
+            None => (entry.to_lowercase(), 1.0),
+        };
+
+        let provider: Arc<dyn TokenRiskProvider> = match name.as_str() {
+            "solsniffer" => match SolSnifferClient::from_env()? {
+                Some(client) => Arc::new(client),
+                None if allow_missing_solsniffer_key => {
+                    eprintln!("⚠️  SOLSNIFER_KEY not set - SolSniffer risk provider disabled");
+                    continue;
+                }
+                None => bail!(
+                    "SOLSNIFER_KEY should be set in .env file (set ALLOW_MISSING_SOLSNIFER_KEY=true to run without it)"
+                ),
+            },
+            "rugcheck" => Arc::new(RugCheckClient::from_env()?),
+            _ => bail!("Unknown risk provider '{}'; expected solsniffer or rugcheck", name),
+        };
+
+        if providers.iter().any(|existing: &WeightedProvider| existing.provider.name() == provider.name()) {
+            bail!("Risk provider '{}' listed twice in RISK_PROVIDERS", name);
+        }
+        println!("🛡️  Risk provider: {} (weight {})", provider.name(), weight);
+        providers.push(WeightedProvider { provider, weight });
+    }
+
+    Ok(match providers.len() {
+        0 => None,
+        1 => providers.pop().map(|weighted| weighted.provider),
+        _ => Some(Arc::new(AggregateProvider::new(providers, honeypot_threshold))),
+    })
+}
+
+// Single entry point for token analysis: cached results first, then the
+// configured risk provider, checked against the mint account when an RPC node
+// is configured. Without a provider (degraded mode) only the on-chain checks
+// run. Provider analyses get their final verdict from the rules file.
+#[derive(Clone)]
+pub struct TokenAnalyzer {
+    provider: Option<Arc<dyn TokenRiskProvider>>,
+    inspector: Option<Arc<MintInspector>>,
+    rules: Arc<RuleSet>,
+    cache: AnalysisCache,
+}
+
+impl TokenAnalyzer {
+    pub fn new(
+        provider: Option<Arc<dyn TokenRiskProvider>>,
+        inspector: Option<Arc<MintInspector>>,
+        rules: Arc<RuleSet>,
+        cache: AnalysisCache,
+    ) -> Self {
+        Self {
+            provider,
+            inspector,
+            rules,
+            cache,
+        }
+    }
+
+    pub fn provider_name(&self) -> Option<&str> {
+        self.provider.as_ref().map(|provider| provider.name())
+    }
+
+    // `holder_exclusions` are the accounts of the pool the token was seen in
+    pub async fn analyze(&self, token_address: &str, holder_exclusions: &[String]) -> Result<TokenData> {
+        // The cache holds unjudged analyses so rule changes apply to them too
+        if let Some(mut token_data) = self.cache.get(token_address) {
+            println!("🗃️  Using cached analysis for {}", token_address);
+            if let Some(holders) = self.cached_mint_holders(&token_data, holder_exclusions).await {
+                apply_holders(&mut token_data, holders);
+            }
+            self.rules.judge_token(&mut token_data);
+            return Ok(token_data);
+        }
+
+        let Some(provider) = &self.provider else {
+            let mint = self
+                .inspect(token_address, holder_exclusions)
+                .await
+                .ok_or_else(|| anyhow!("Risk analysis disabled - no risk provider configured"))?
+                .with_context(|| format!("On-chain mint check failed for {}", token_address))?;
+            // Not cached: a later run with a provider should analyse the token properly
+            let token_data = onchain_token_data(token_address, mint);
+            report(&token_data);
+            return Ok(token_data);
+        };
+
+        println!("🚀 Analyzing {} with {}", token_address, provider.name());
+        let (assessment, mint) = tokio::join!(
+            provider.assess(token_address),
+            self.inspect(token_address, holder_exclusions)
+        );
+        let mut token_data = assessment?;
+
+        let holders = match mint {
+            Some(Ok(mut mint)) => {
+                let holders = mint.holders.take();
+                apply_onchain_mint(&mut token_data, mint);
+                holders
+            }
+            // The report still stands on its own
+            Some(Err(e)) => {
+                eprintln!("⚠️  On-chain mint check failed for {}: {:#}", token_address, e);
+                None
+            }
+            None => None,
+        };
+
+        // Holder figures depend on the pool's exclusions, so they stay out of
+        // the cache and are recomputed for every pool the mint shows up in
+        self.cache.insert(token_address, token_data.clone()).await;
+        if let Some(holders) = holders {
+            apply_holders(&mut token_data, holders);
+        }
+        self.rules.judge_token(&mut token_data);
+        report(&token_data);
+
+        Ok(token_data)
+    }
+
+    // Mint account and holder concentration; None without an RPC node
+    async fn inspect(&self, token_address: &str, holder_exclusions: &[String]) -> Option<Result<OnChainMint>> {
+        let inspector = self.inspector.as_ref()?;
+        let mut mint = inspector.fetch_mint(token_address).await;
+        if let Ok(mint) = &mut mint {
+            mint.holders = self.holders(inspector, token_address, mint, holder_exclusions).await;
+        }
+        Some(mint)
+    }
+
+    // Holder concentration for a cached analysis that was checked on chain
+    async fn cached_mint_holders(
+        &self,
+        token_data: &TokenData,
+        holder_exclusions: &[String],
+    ) -> Option<HolderConcentration> {
+        let inspector = self.inspector.as_ref()?;
+        let mint = token_data.onchain.as_ref()?;
+        self.holders(inspector, &token_data.address, mint, holder_exclusions).await
+    }
+
+    async fn holders(
+        &self,
+        inspector: &MintInspector,
+        token_address: &str,
+        mint: &OnChainMint,
+        holder_exclusions: &[String],
+    ) -> Option<HolderConcentration> {
+        match inspector.analyze_holders(token_address, mint, holder_exclusions).await {
+            Ok(holders) => Some(holders),
+            Err(e) => {
+                eprintln!("⚠️  Holder analysis failed for {}: {:#}", token_address, e);
+                None
+            }
+        }
+    }
+
+    // Pool-scoped rules, re-run whenever the event changes before a publish
+    pub fn judge_pool(&self, pool_event: &mut PoolCreationEvent) {
+        self.rules.judge_pool(pool_event);
+    }
+}
+
+fn report(token_data: &TokenData) {
+    print_analysis(token_data);
+    if token_data.is_honeypot {
+        println!("🚨 HONEYPOT ALERT: This token shows signs of being a honeypot!");
+    }
+    if token_data.risk_level == RiskLevel::Critical {
+        println!("🚨 CRITICAL RISK: Avoid this token!");
+    }
+}
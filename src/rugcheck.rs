@@ -0,0 +1,207 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::Value;
+use tokio::time::Duration;
+
+use crate::rate_limit::{send_with_retries, RateLimiter};
+use crate::risk::TokenRiskProvider;
+use crate::solsniffer::{AnalysisStatus, RiskLevel, TokenData};
+
+const RUGCHECK_API_URL: &str = "https://api.rugcheck.xyz/v1/tokens";
+
+// The public API is unauthenticated and throttled per IP; override with
+// RUGCHECK_REQUESTS_PER_MINUTE / RUGCHECK_BURST
+const DEFAULT_REQUESTS_PER_MINUTE: u32 = 30;
+const DEFAULT_BURST: u32 = 3;
+
+// An LP this locked or burned counts as burned
+const LP_LOCKED_PCT: f64 = 99.0;
+
+// RugCheck token reports. RugCheck scores risk (higher is worse), so its
+// normalised score is inverted onto our 0-100 safety scale.
+pub struct RugCheckClient {
+    client: Client,
+    api_key: Option<String>,
+    rate_limiter: RateLimiter,
+}
+
+impl RugCheckClient {
+    // RUGCHECK_API_KEY is optional and only raises the rate limit
+    pub fn from_env() -> Result<Self> {
+        let rate_limiter = RateLimiter::from_env(
+            "RugCheck",
+            "RUGCHECK",
+            DEFAULT_REQUESTS_PER_MINUTE,
+            DEFAULT_BURST,
+        )?;
+
+        Self::new(std::env::var("RUGCHECK_API_KEY").ok(), rate_limiter)
+    }
+
+    pub fn new(api_key: Option<String>, rate_limiter: RateLimiter) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .pool_idle_timeout(Duration::from_secs(90))
+            .pool_max_idle_per_host(8)
+            .build()
+            .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))?;
+
+        Ok(Self { client, api_key, rate_limiter })
+    }
+
+    pub async fn analyze_token(&self, token_address: &str) -> Result<TokenData> {
+        let url = format!("{}/{}/report", RUGCHECK_API_URL, token_address);
+        let response = send_with_retries(
+            &self.rate_limiter,
+            &format!("RugCheck lookup for {}", token_address),
+            || {
+                let request = self.client.get(&url).header("accept", "application/json");
+                match &self.api_key {
+                    Some(api_key) => request.header("X-API-KEY", api_key),
+                    None => request,
+                }
+            },
+        )
+        .await?;
+
+        let json: Value = response
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse JSON: {}", e))?;
+
+        parse_report(token_address, &json)
+    }
+}
+
+#[async_trait]
+impl TokenRiskProvider for RugCheckClient {
+    fn name(&self) -> &str {
+        "RugCheck"
+    }
+
+    async fn assess(&self, token_address: &str) -> Result<TokenData> {
+        self.analyze_token(token_address).await
+    }
+}
+
+fn parse_report(token_address: &str, json: &Value) -> Result<TokenData> {
+    let risk_score = json
+        .get("score_normalised")
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| anyhow!("Missing score_normalised in RugCheck report"))?;
+    let score = (100.0 - risk_score).clamp(0.0, 100.0).round() as u32;
+
+    let token_meta = json.get("tokenMeta");
+    let token = json.get("token");
+
+    let token_name = token_meta
+        .and_then(|meta| meta.get("name"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("Unknown")
+        .to_string();
+
+    let token_symbol = token_meta
+        .and_then(|meta| meta.get("symbol"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("UNKNOWN")
+        .to_string();
+
+    let decimals = token
+        .and_then(|token| token.get("decimals"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(9) as u8;
+
+    let supply_amount = token
+        .and_then(|token| token.get("supply"))
+        .and_then(|v| v.as_f64())
+        .map(|supply| supply / 10f64.powi(decimals as i32))
+        .unwrap_or(0.0);
+
+    let price = json.get("price").and_then(|v| v.as_f64()).unwrap_or(0.0);
+
+    // A null authority means it was revoked
+    let mint_disabled = json.get("mintAuthority").is_some_and(Value::is_null);
+    let freeze_disabled = json.get("freezeAuthority").is_some_and(Value::is_null);
+
+    let lp_burned = json
+        .get("markets")
+        .and_then(|v| v.as_array())
+        .is_some_and(|markets| {
+            markets.iter().any(|market| {
+                market
+                    .get("lp")
+                    .and_then(|lp| lp.get("lpLockedPct"))
+                    .and_then(|v| v.as_f64())
+                    .is_some_and(|locked| locked >= LP_LOCKED_PCT)
+            })
+        });
+
+    let top_10_percentage = json
+        .get("topHolders")
+        .and_then(|v| v.as_array())
+        .map(|holders| {
+            holders
+                .iter()
+                .take(10)
+                .filter_map(|holder| holder.get("pct").and_then(|v| v.as_f64()))
+                .sum()
+        })
+        .unwrap_or(0.0);
+
+    let risks = json
+        .get("risks")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    let danger_risks = risks
+        .iter()
+        .filter(|risk| risk.get("level").and_then(|v| v.as_str()) == Some("danger"))
+        .count() as u32;
+
+    let audit_risks = risks
+        .iter()
+        .filter_map(|risk| {
+            let name = risk.get("name").and_then(|v| v.as_str())?;
+            let level = risk.get("level").and_then(|v| v.as_str()).unwrap_or("info");
+            Some(format!("{} ({})", name, level))
+        })
+        .collect();
+
+    let rugged = json.get("rugged").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    Ok(TokenData {
+        address: token_address.to_string(),
+        token_name,
+        token_symbol,
+        decimals,
+        market_cap: price * supply_amount,
+        score,
+        risk_level: RiskLevel::from_score(score),
+        price,
+        supply_amount,
+        liquidity_total: json
+            .get("totalMarketLiquidity")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.0),
+        top_10_percentage,
+        holder_count: json.get("totalHolders").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
+        // Only RugCheck's own rug verdict; how many dangers make a honeypot is up to the rules
+        is_honeypot: rugged,
+        high_risk_count: danger_risks,
+        audit_risks,
+        deploy_time: json
+            .get("detectedAt")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+        mint_disabled,
+        freeze_disabled,
+        lp_burned,
+        analysis_status: AnalysisStatus::Analyzed,
+        provider_scores: Vec::new(),
+        onchain: None,
+        fired_rules: Vec::new(),
+    })
+}
@@ -0,0 +1,582 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::parsing::PoolCreationEvent;
+use crate::solsniffer::{RiskLevel, TokenData};
+
+const DEFAULT_RULES_CONFIG_PATH: &str = "config/rules.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleScope {
+    // Evaluated against each analysed TokenData
+    Token,
+    // Evaluated against the PoolCreationEvent every time it is published
+    Pool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Op {
+    #[serde(rename = "==")]
+    Eq,
+    #[serde(rename = "!=")]
+    Ne,
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = ">=")]
+    Gte,
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = "<=")]
+    Lte,
+    #[serde(rename = "in")]
+    In,
+    #[serde(rename = "not_in")]
+    NotIn,
+    // Array element or substring
+    #[serde(rename = "contains")]
+    Contains,
+    // Field present and not null; takes no value
+    #[serde(rename = "exists")]
+    Exists,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Condition {
+    // Dotted path into the JSON form of the token or pool, e.g. "lp.state"
+    pub field: String,
+    pub op: Op,
+    #[serde(default)]
+    pub value: Option<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    pub scope: RuleScope,
+    #[serde(default)]
+    pub description: Option<String>,
+    // Evidence towards a honeypot verdict
+    #[serde(default)]
+    pub honeypot_weight: f64,
+    // Points taken off the safety score
+    #[serde(default)]
+    pub score_penalty: u32,
+    // All must hold for the rule to fire
+    pub when: Vec<Condition>,
+}
+
+// Score bands are inclusive upper bounds on the 0-100 safety score
+#[derive(Debug, Clone, Deserialize)]
+pub struct VerdictConfig {
+    pub honeypot_threshold: f64,
+    pub critical_max_score: u32,
+    pub high_max_score: u32,
+    pub medium_max_score: u32,
+}
+
+// Outcome of the pool-scoped rules. Pool rules start from a clean score of
+// 100, and the verdict can only make the pool look worse than its tokens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolVerdict {
+    pub fired_rules: Vec<String>,
+    pub honeypot_weight: f64,
+    pub score: u32,
+    pub risk_level: RiskLevel,
+    pub is_honeypot: bool,
+}
+
+// Declarative honeypot and risk rules, loaded once at startup
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleSet {
+    pub verdict: VerdictConfig,
+    #[serde(rename = "rule", default)]
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    // RULES_CONFIG_PATH overrides the default config/rules.toml
+    pub fn from_env() -> Result<Self> {
+        let path = std::env::var("RULES_CONFIG_PATH")
+            .unwrap_or_else(|_| DEFAULT_RULES_CONFIG_PATH.to_string());
+        Self::load(&path)
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read rules config '{}': {}", path, e))?;
+        let rule_set: RuleSet = toml::from_str(&contents)
+            .map_err(|e| anyhow!("Invalid rules config '{}': {}", path, e))?;
+        rule_set
+            .validate()
+            .map_err(|e| anyhow!("Invalid rules config '{}': {}", path, e))?;
+
+        println!("⚖️  Loaded {} risk rules from {}", rule_set.rules.len(), path);
+        for rule in &rule_set.rules {
+            println!(
+                "   • {} ({:?}){}",
+                rule.id,
+                rule.scope,
+                rule.description
+                    .as_deref()
+                    .map(|description| format!(": {}", description))
+                    .unwrap_or_default()
+            );
+        }
+
+        Ok(rule_set)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let verdict = &self.verdict;
+        if verdict.honeypot_threshold.is_nan() || verdict.honeypot_threshold <= 0.0 {
+            bail!("verdict.honeypot_threshold must be positive");
+        }
+        if !(verdict.critical_max_score < verdict.high_max_score
+            && verdict.high_max_score < verdict.medium_max_score
+            && verdict.medium_max_score < 100)
+        {
+            bail!("verdict score bands must satisfy critical < high < medium < 100");
+        }
+
+        let mut ids = HashSet::new();
+        for rule in &self.rules {
+            if !ids.insert(rule.id.as_str()) {
+                bail!("Duplicate rule id '{}'", rule.id);
+            }
+            if rule.when.is_empty() {
+                bail!("Rule '{}' has no conditions", rule.id);
+            }
+            if rule.honeypot_weight.is_nan() || rule.honeypot_weight < 0.0 {
+                bail!("Rule '{}' has a negative honeypot_weight", rule.id);
+            }
+            for condition in &rule.when {
+                match (condition.op, &condition.value) {
+                    (Op::Exists, Some(_)) => {
+                        bail!("Rule '{}': 'exists' on {} takes no value", rule.id, condition.field)
+                    }
+                    (Op::In | Op::NotIn, Some(Value::Array(_))) | (Op::Exists, None) => {}
+                    (Op::In | Op::NotIn, _) => {
+                        bail!("Rule '{}': '{:?}' on {} needs a list", rule.id, condition.op, condition.field)
+                    }
+                    (_, None) => bail!("Rule '{}': condition on {} needs a value", rule.id, condition.field),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn risk_level(&self, score: u32) -> RiskLevel {
+        let verdict = &self.verdict;
+        if score <= verdict.critical_max_score {
+            RiskLevel::Critical
+        } else if score <= verdict.high_max_score {
+            RiskLevel::High
+        } else if score <= verdict.medium_max_score {
+            RiskLevel::Medium
+        } else {
+            RiskLevel::Low
+        }
+    }
+
+    // Replaces the token's honeypot flag and risk level with the rules'
+    // verdict. The provider's own flag is an input like any other field.
+    pub fn judge_token(&self, token_data: &mut TokenData) {
+        let Ok(json) = serde_json::to_value(&*token_data) else {
+            return;
+        };
+        let fired = self.fired(RuleScope::Token, &json);
+
+        let honeypot_weight: f64 = fired.iter().map(|rule| rule.honeypot_weight).sum();
+        let penalty: u32 = fired.iter().map(|rule| rule.score_penalty).sum();
+
+        token_data.score = token_data.score.saturating_sub(penalty);
+        token_data.risk_level = self.risk_level(token_data.score);
+        token_data.is_honeypot = honeypot_weight >= self.verdict.honeypot_threshold;
+        token_data.fired_rules = fired.iter().map(|rule| rule.id.clone()).collect();
+    }
+
+    // Recomputed from scratch every time the pool changes
+    pub fn judge_pool(&self, pool_event: &mut PoolCreationEvent) {
+        pool_event.verdict = None;
+        let Ok(json) = serde_json::to_value(&*pool_event) else {
+            return;
+        };
+        let fired = self.fired(RuleScope::Pool, &json);
+        if fired.is_empty() {
+            return;
+        }
+
+        let honeypot_weight: f64 = fired.iter().map(|rule| rule.honeypot_weight).sum();
+        let score = 100u32.saturating_sub(fired.iter().map(|rule| rule.score_penalty).sum());
+        let verdict = PoolVerdict {
+            fired_rules: fired.iter().map(|rule| rule.id.clone()).collect(),
+            honeypot_weight,
+            score,
+            risk_level: self.risk_level(score),
+            is_honeypot: honeypot_weight >= self.verdict.honeypot_threshold,
+        };
+
+        println!(
+            "⚖️  Pool rules fired for {}: {}",
+            pool_event.transaction_signature,
+            verdict.fired_rules.join(", ")
+        );
+        pool_event.verdict = Some(verdict);
+    }
+
+    fn fired(&self, scope: RuleScope, json: &Value) -> Vec<&Rule> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.scope == scope)
+            .filter(|rule| rule.when.iter().all(|condition| condition.holds(json)))
+            .collect()
+    }
+}
+
+impl Condition {
+    fn holds(&self, json: &Value) -> bool {
+        let field = self
+            .field
+            .split('.')
+            .try_fold(json, |value, key| value.get(key))
+            .filter(|value| !value.is_null());
+
+        let Some(field) = field else {
+            // A missing field satisfies nothing but a negation
+            return matches!(self.op, Op::Ne | Op::NotIn);
+        };
+        let Some(expected) = &self.value else {
+            return self.op == Op::Exists;
+        };
+
+        match self.op {
+            Op::Eq => values_equal(field, expected),
+            Op::Ne => !values_equal(field, expected),
+            Op::Gt | Op::Gte | Op::Lt | Op::Lte => match (as_number(field), as_number(expected)) {
+                (Some(field), Some(expected)) => match self.op {
+                    Op::Gt => field > expected,
+                    Op::Gte => field >= expected,
+                    Op::Lt => field < expected,
+                    _ => field <= expected,
+                },
+                _ => false,
+            },
+            Op::In => list(expected).any(|item| values_equal(field, item)),
+            Op::NotIn => !list(expected).any(|item| values_equal(field, item)),
+            Op::Contains => match field {
+                Value::Array(items) => items.iter().any(|item| values_equal(item, expected)),
+                Value::String(text) => expected.as_str().is_some_and(|needle| text.contains(needle)),
+                _ => false,
+            },
+            Op::Exists => true,
+        }
+    }
+}
+
+fn list(value: &Value) -> impl Iterator<Item = &Value> {
+    value.as_array().into_iter().flatten()
+}
+
+// Several numeric fields (raw amounts, formatted SOL) are carried as strings
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+fn values_equal(field: &Value, expected: &Value) -> bool {
+    match (as_number(field), as_number(expected)) {
+        (Some(field), Some(expected)) if field.is_finite() => field == expected,
+        _ => field == expected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const VERDICT: &str = r#"
+        [verdict]
+        honeypot_threshold = 2.0
+        critical_max_score = 25
+        high_max_score = 50
+        medium_max_score = 75
+    "#;
+
+    fn rule_set(rules: &str) -> Result<RuleSet> {
+        let rule_set: RuleSet = toml::from_str(&format!("{}\n{}", VERDICT, rules))?;
+        rule_set.validate()?;
+        Ok(rule_set)
+    }
+
+    fn condition(field: &str, op: Op, value: Option<Value>) -> Condition {
+        Condition {
+            field: field.to_string(),
+            op,
+            value,
+        }
+    }
+
+    fn holds(op: Op, value: Value, json: &Value) -> bool {
+        condition("field", op, Some(value)).holds(json)
+    }
+
+    fn token(score: u32, mint_disabled: bool, top_10_percentage: f64) -> TokenData {
+        serde_json::from_value(json!({
+            "address": "Mint",
+            "token_name": "Token",
+            "token_symbol": "TKN",
+            "decimals": 6,
+            "market_cap": 0.0,
+            "score": score,
+            "risk_level": "Low",
+            "price": 0.0,
+            "supply_amount": 0.0,
+            "liquidity_total": 0.0,
+            "top_10_percentage": top_10_percentage,
+            "holder_count": 0,
+            "is_honeypot": false,
+            "audit_risks": [],
+            "deploy_time": "Unknown",
+            "mint_disabled": mint_disabled,
+            "freeze_disabled": true,
+            "lp_burned": true
+        }))
+        .expect("valid token")
+    }
+
+    fn pool(lp_state: &str) -> PoolCreationEvent {
+        let token = serde_json::to_value(token(90, true, 10.0)).expect("token json");
+        serde_json::from_value(json!({
+            "dex_name": "Raydium",
+            "pool_type": "AmmV4",
+            "pool_address": null,
+            "token_a": token,
+            "token_b": token,
+            "timestamp": "2026-01-01T00:00:00Z",
+            "transaction_signature": "Signature",
+            "liquidity_amounts": {
+                "token_a_amount": null,
+                "token_b_amount": null,
+                "sol_amount": null,
+                "token_a_amount_formatted": null,
+                "token_b_amount_formatted": null,
+                "sol_amount_formatted": null
+            },
+            "pool_params": {
+                "sqrt_price_x64": null,
+                "initial_price": null,
+                "tick_spacing": null,
+                "fee_tier": null,
+                "fee_rate_bps": null,
+                "bin_step": null,
+                "active_id": null
+            },
+            "coin_type": "ImmediateCoin",
+            "launch": null,
+            "lp": {
+                "lp_mint": "LpMint",
+                "creator_lp_account": null,
+                "state": lp_state,
+                "burned_percentage": 0.0,
+                "locked_percentage": 0.0,
+                "creator_percentage": 0.0,
+                "lockers": [],
+                "peak_supply": null,
+                "checked_at": null
+            }
+        }))
+        .expect("valid pool")
+    }
+
+    #[test]
+    fn equality_compares_numbers_across_strings() {
+        let json = json!({ "field": "42" });
+        assert!(holds(Op::Eq, json!(42), &json));
+        assert!(!holds(Op::Eq, json!(43), &json));
+        assert!(holds(Op::Ne, json!(43), &json));
+        assert!(holds(Op::Eq, json!(true), &json!({ "field": true })));
+    }
+
+    #[test]
+    fn ordering_operators() {
+        let json = json!({ "field": 80.5 });
+        assert!(holds(Op::Gt, json!(80), &json));
+        assert!(!holds(Op::Gt, json!(80.5), &json));
+        assert!(holds(Op::Gte, json!(80.5), &json));
+        assert!(holds(Op::Lt, json!(81), &json));
+        assert!(!holds(Op::Lt, json!(80.5), &json));
+        assert!(holds(Op::Lte, json!(80.5), &json));
+        // Non-numeric values never compare
+        assert!(!holds(Op::Gt, json!(1), &json!({ "field": "many" })));
+    }
+
+    #[test]
+    fn list_and_contains_operators() {
+        let json = json!({ "field": "Unlocked", "risks": ["mint", "freeze"] });
+        assert!(holds(Op::In, json!(["HeldByCreator", "Unlocked"]), &json));
+        assert!(!holds(Op::NotIn, json!(["HeldByCreator", "Unlocked"]), &json));
+        assert!(holds(Op::NotIn, json!(["Burned"]), &json));
+        assert!(holds(Op::Contains, json!("lock"), &json));
+        assert!(condition("risks", Op::Contains, Some(json!("freeze"))).holds(&json));
+        assert!(!condition("risks", Op::Contains, Some(json!("fee"))).holds(&json));
+    }
+
+    #[test]
+    fn exists_and_missing_fields() {
+        let json = json!({ "lp": { "state": "Burned" }, "launch": null });
+        assert!(condition("lp.state", Op::Exists, None).holds(&json));
+        assert!(!condition("launch", Op::Exists, None).holds(&json));
+        assert!(!condition("missing", Op::Exists, None).holds(&json));
+
+        // A missing or null field only satisfies the negations
+        assert!(!condition("lp.locker", Op::Eq, Some(json!("x"))).holds(&json));
+        assert!(!condition("launch", Op::Gt, Some(json!(0))).holds(&json));
+        assert!(condition("lp.locker", Op::Ne, Some(json!("x"))).holds(&json));
+        assert!(condition("launch", Op::NotIn, Some(json!(["x"]))).holds(&json));
+    }
+
+    #[test]
+    fn validate_rejects_bad_rules() {
+        let rule = |scope: &str, when: &str| {
+            format!("[[rule]]\nid = \"r\"\nscope = \"{}\"\n[[rule.when]]\n{}", scope, when)
+        };
+
+        assert!(rule_set(&rule("token", "field = \"score\"\nop = \">\"\nvalue = 1")).is_ok());
+        // Unknown scope or operator fails to parse
+        assert!(rule_set(&rule("wallet", "field = \"score\"\nop = \">\"\nvalue = 1")).is_err());
+        assert!(rule_set(&rule("token", "field = \"score\"\nop = \"~\"\nvalue = 1")).is_err());
+        // Operator and value must agree
+        assert!(rule_set(&rule("token", "field = \"score\"\nop = \">\"")).is_err());
+        assert!(rule_set(&rule("token", "field = \"lp\"\nop = \"exists\"\nvalue = 1")).is_err());
+        assert!(rule_set(&rule("pool", "field = \"lp.state\"\nop = \"in\"\nvalue = \"Burned\"")).is_err());
+        // No conditions, duplicate ids, negative weights
+        assert!(rule_set("[[rule]]\nid = \"r\"\nscope = \"token\"\nwhen = []").is_err());
+        let valid = rule("token", "field = \"score\"\nop = \">\"\nvalue = 1");
+        assert!(rule_set(&format!("{}\n{}", valid, valid)).is_err());
+        assert!(rule_set(&valid.replace("scope", "honeypot_weight = -1.0\nscope")).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_bad_verdict_bands() {
+        let rule_set = |verdict: &str| {
+            toml::from_str::<RuleSet>(verdict)
+                .map_err(anyhow::Error::from)
+                .and_then(|rule_set| rule_set.validate())
+        };
+        let bands = |threshold: f64, critical: u32, high: u32, medium: u32| {
+            format!(
+                "[verdict]\nhoneypot_threshold = {:?}\ncritical_max_score = {}\nhigh_max_score = {}\nmedium_max_score = {}",
+                threshold, critical, high, medium
+            )
+        };
+
+        assert!(rule_set(&bands(3.0, 25, 50, 75)).is_ok());
+        assert!(rule_set(&bands(0.0, 25, 50, 75)).is_err());
+        assert!(rule_set(&bands(3.0, 50, 50, 75)).is_err());
+        assert!(rule_set(&bands(3.0, 25, 50, 100)).is_err());
+    }
+
+    #[test]
+    fn risk_level_bands_are_inclusive_upper_bounds() {
+        let rules = rule_set("").expect("valid rules");
+        assert_eq!(rules.risk_level(0), RiskLevel::Critical);
+        assert_eq!(rules.risk_level(25), RiskLevel::Critical);
+        assert_eq!(rules.risk_level(26), RiskLevel::High);
+        assert_eq!(rules.risk_level(50), RiskLevel::High);
+        assert_eq!(rules.risk_level(75), RiskLevel::Medium);
+        assert_eq!(rules.risk_level(76), RiskLevel::Low);
+        assert_eq!(rules.risk_level(100), RiskLevel::Low);
+    }
+
+    const TOKEN_RULES: &str = r#"
+        [[rule]]
+        id = "mint_active"
+        scope = "token"
+        honeypot_weight = 1.0
+        score_penalty = 20
+        [[rule.when]]
+        field = "mint_disabled"
+        op = "=="
+        value = false
+
+        [[rule]]
+        id = "concentrated"
+        scope = "token"
+        honeypot_weight = 1.0
+        [[rule.when]]
+        field = "top_10_percentage"
+        op = ">"
+        value = 80
+
+        [[rule]]
+        id = "lp_pullable"
+        scope = "pool"
+        score_penalty = 60
+        [[rule.when]]
+        field = "lp.state"
+        op = "in"
+        value = ["HeldByCreator", "Unlocked"]
+    "#;
+
+    #[test]
+    fn judge_token_records_fired_rules_and_verdict() {
+        let rules = rule_set(TOKEN_RULES).expect("valid rules");
+
+        let mut clean = token(90, true, 10.0);
+        rules.judge_token(&mut clean);
+        assert!(clean.fired_rules.is_empty());
+        assert_eq!(clean.score, 90);
+        assert_eq!(clean.risk_level, RiskLevel::Low);
+        assert!(!clean.is_honeypot);
+
+        let mut one = token(90, false, 10.0);
+        rules.judge_token(&mut one);
+        assert_eq!(one.fired_rules, vec!["mint_active"]);
+        assert_eq!(one.score, 70);
+        assert_eq!(one.risk_level, RiskLevel::Medium);
+        assert!(!one.is_honeypot);
+
+        // Weights add up to the threshold; pool rules are not applied to tokens
+        let mut both = token(90, false, 95.0);
+        rules.judge_token(&mut both);
+        assert_eq!(both.fired_rules, vec!["mint_active", "concentrated"]);
+        assert!(both.is_honeypot);
+    }
+
+    #[test]
+    fn judge_pool_recomputes_the_verdict() {
+        let rules = rule_set(TOKEN_RULES).expect("valid rules");
+
+        let mut pool_event = pool("HeldByCreator");
+        rules.judge_pool(&mut pool_event);
+        let verdict = pool_event.verdict.as_ref().expect("pool rule fired");
+        assert_eq!(verdict.fired_rules, vec!["lp_pullable"]);
+        assert_eq!(verdict.score, 40);
+        assert_eq!(verdict.risk_level, RiskLevel::High);
+        assert!(!verdict.is_honeypot);
+        assert_eq!(pool_event.risk_level(), RiskLevel::High);
+
+        // The LP got burned: the earlier verdict is cleared
+        pool_event.lp.as_mut().expect("lp").state = crate::lp::LpState::Burned;
+        rules.judge_pool(&mut pool_event);
+        assert!(pool_event.verdict.is_none());
+    }
+
+    #[test]
+    fn shipped_rules_config_is_valid() {
+        let rules = RuleSet::load(DEFAULT_RULES_CONFIG_PATH).expect("config/rules.toml loads");
+        assert!(rules.rules.iter().any(|rule| rule.scope == RuleScope::Token));
+        assert!(rules.rules.iter().any(|rule| rule.scope == RuleScope::Pool));
+    }
+}
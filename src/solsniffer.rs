@@ -1,9 +1,19 @@
-use reqwest::Client;
+use async_trait::async_trait;
+use reqwest::{Client, Response};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use anyhow::{Result, anyhow};
-use tokio::time::{Duration};use dotenv::dotenv;
+use tokio::time::{Duration};
 use std::env;
+use crate::onchain::OnChainMint;
+use crate::rate_limit::{send_with_retries, RateLimiter};
+use crate::risk::TokenRiskProvider;
+
+const SOLSNIFFER_API_URL: &str = "https://solsniffer.com/api/v2/token";
+
+// Defaults match the SolSniffer plan; override with SOLSNIFER_REQUESTS_PER_MINUTE / SOLSNIFER_BURST
+const DEFAULT_REQUESTS_PER_MINUTE: u32 = 60;
+const DEFAULT_BURST: u32 = 5;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct TokenData {
@@ -20,19 +30,74 @@ pub struct TokenData {
     pub top_10_percentage: f64,
     pub holder_count: u32,
     pub is_honeypot: bool,
+    // Indicators the provider rated high severity
+    #[serde(default)]
+    pub high_risk_count: u32,
     pub audit_risks: Vec<String>,
     pub deploy_time: String,
     pub mint_disabled: bool,
     pub freeze_disabled: bool,
     pub lp_burned: bool,
+    // Older cached analyses predate this field and were all real analyses
+    #[serde(default)]
+    pub analysis_status: AnalysisStatus,
+    // Each backend's verdict when several risk providers were combined
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub provider_scores: Vec<ProviderScore>,
+    // Mint state read over RPC, when on-chain checks are enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub onchain: Option<OnChainMint>,
+    // Ids of the rules from config/rules.toml behind the verdict
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fired_rules: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderScore {
+    pub provider: String,
+    pub weight: f64,
+    pub score: u32,
+    pub risk_level: RiskLevel,
+    pub is_honeypot: bool,
+}
+
+// Whether the risk fields of a TokenData come from an actual analysis
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnalysisStatus {
+    #[default]
+    Analyzed,
+    // Well-known token (e.g. WSOL) that is never sent for analysis
+    Trusted,
+    // No risk provider is configured: mint state, extensions and holders come
+    // from the chain, every other risk field is a placeholder
+    OnChainOnly,
+    // Published before the analysis finished; a pool_enriched event follows
+    Pending,
+    // The analysis could not be obtained; risk fields are placeholders
+    Unavailable,
+}
+
+impl TokenData {
+    // Decimals are only real when they came from an analysis or the mint
+    pub fn known_decimals(&self) -> Option<u8> {
+        match self.analysis_status {
+            AnalysisStatus::Analyzed | AnalysisStatus::Trusted | AnalysisStatus::OnChainOnly => {
+                Some(self.decimals)
+            }
+            AnalysisStatus::Pending | AnalysisStatus::Unavailable => None,
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+// Unknown ranks above Critical: a token we could not analyse is never
+// considered safer than one we did
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum RiskLevel {
     Low,
     Medium, 
     High,
     Critical,
+    Unknown,
 }
 
 impl RiskLevel {
@@ -42,10 +107,12 @@ impl RiskLevel {
             RiskLevel::Medium => "🟡", 
             RiskLevel::High => "🟠",
             RiskLevel::Critical => "🔴",
+            RiskLevel::Unknown => "⚪",
         }
     }
 
-    fn from_score(score: u32) -> Self {
+    // Provider-level default; the bands in config/rules.toml give the final level
+    pub fn from_score(score: u32) -> Self {
         match score {
             0..=25 => RiskLevel::Critical,
             26..=50 => RiskLevel::High,
@@ -60,41 +127,56 @@ impl RiskLevel {
 pub struct SolSnifferClient {
     client: Client,
     api_key: String,
+    rate_limiter: RateLimiter,
 }
 
 impl SolSnifferClient {
-    pub fn new() -> Result<Self> {
-        dotenv().ok();
-    
-        let api_key = env::var("SOLSNIFER_KEY")
-        .map_err(|_| anyhow!("SOLSNIFER_KEY should be set in .env file"))?;
+    // Built once at startup and shared, so lookups reuse pooled connections.
+    // Returns None when SOLSNIFER_KEY is not set.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(api_key) = env::var("SOLSNIFER_KEY") else {
+            return Ok(None);
+        };
+        let rate_limiter = RateLimiter::from_env(
+            "SolSniffer",
+            "SOLSNIFER",
+            DEFAULT_REQUESTS_PER_MINUTE,
+            DEFAULT_BURST,
+        )?;
+
+        Self::new(api_key, rate_limiter).map(Some)
+    }
 
+    pub fn new(api_key: String, rate_limiter: RateLimiter) -> Result<Self> {
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
+            .pool_idle_timeout(Duration::from_secs(90))
+            .pool_max_idle_per_host(8)
             .user_agent("SolSniffer-Integration/1.0.0")
             .build()
             .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))?;
 
-        Ok(Self { client, api_key })
+        Ok(Self { client, api_key, rate_limiter })
     }
 
     pub async fn analyze_token(&self, token_address: &str) -> Result<TokenData> {
-        let url = format!("https://solsniffer.com/api/v2/token/{}", token_address);
-        
-        let response = self.client
-            .get(&url)
-            .header("accept", "application/json")
-            .header("X-API-KEY", &self.api_key)
-            .send()
-            .await
-            .map_err(|e| anyhow!("Failed to send request: {}", e))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_body = response.text().await.unwrap_or_default();
-            return Err(anyhow!("API request failed: {} - {}", status, error_body));
-        }
+        let url = format!("{}/{}", SOLSNIFFER_API_URL, token_address);
+        let response = send_with_retries(
+            &self.rate_limiter,
+            &format!("SolSniffer lookup for {}", token_address),
+            || {
+                self.client
+                    .get(&url)
+                    .header("accept", "application/json")
+                    .header("X-API-KEY", &self.api_key)
+            },
+        )
+        .await?;
+
+        self.read_token_data(response).await
+    }
 
+    async fn read_token_data(&self, response: Response) -> Result<TokenData> {
         let body = response.text().await
             .map_err(|e| anyhow!("Failed to read response body: {}", e))?;
         
@@ -181,8 +263,12 @@ impl SolSnifferClient {
         // Extract audit risks
         let audit_risks = self.extract_audit_risks(token_data)?;
 
-        // Honeypot detection
-        let is_honeypot = self.detect_honeypot(token_data, &audit_risks)?;
+        let high_risk_count = token_data
+            .get("indicatorData")
+            .and_then(|indicator_data| indicator_data.get("high"))
+            .and_then(|high_risks| high_risks.get("count"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as u32;
 
         Ok(TokenData {
             address,
@@ -197,12 +283,18 @@ impl SolSnifferClient {
             liquidity_total,
             top_10_percentage,
             holder_count,
-            is_honeypot,
+            // SolSniffer has no honeypot flag of its own; the rules decide
+            is_honeypot: false,
+            high_risk_count,
             audit_risks,
             deploy_time,
             mint_disabled,
             freeze_disabled,
             lp_burned,
+            analysis_status: AnalysisStatus::Analyzed,
+            provider_scores: Vec::new(),
+            onchain: None,
+            fired_rules: Vec::new(),
         })
     }
 
@@ -269,101 +361,87 @@ impl SolSnifferClient {
 
         Ok(risks)
     }
+}
 
-    fn detect_honeypot(&self, token_data: &Value, _audit_risks: &[String]) -> Result<bool> {
-        let mut honeypot_indicators = 0;
-
-        // Check mint authority (not disabled = potential honeypot)
-        if let Some(audit_risk) = token_data.get("auditRisk") {
-            if let Some(mint_disabled) = audit_risk.get("mintDisabled").and_then(|v| v.as_bool()) {
-                if !mint_disabled {
-                    honeypot_indicators += 1;
-                }
-            }
+#[async_trait]
+impl TokenRiskProvider for SolSnifferClient {
+    fn name(&self) -> &str {
+        "SolSniffer"
+    }
 
-            if let Some(freeze_disabled) = audit_risk.get("freezeDisabled").and_then(|v| v.as_bool()) {
-                if !freeze_disabled {
-                    honeypot_indicators += 1;
-                }
-            }
+    async fn assess(&self, token_address: &str) -> Result<TokenData> {
+        self.analyze_token(token_address).await
+    }
+}
 
-            if let Some(lp_burned) = audit_risk.get("lpBurned").and_then(|v| v.as_bool()) {
-                if !lp_burned {
-                    honeypot_indicators += 1;
-                }
-            }
+pub fn print_analysis(token_data: &TokenData) {
+    println!("\n{} ===== TOKEN SECURITY ANALYSIS =====", token_data.risk_level.as_emoji());
+    println!("📍 Token: {} ({})", token_data.token_name, token_data.token_symbol);
+    println!("🔗 Address: {}", token_data.address);
+    println!("📊 Risk Score: {}/100 ({:?})", token_data.score, token_data.risk_level);
+    println!("🍯 Honeypot Risk: {}", if token_data.is_honeypot { "⚠️  HIGH" } else { "✅ LOW" });
+    if !token_data.fired_rules.is_empty() {
+        println!("⚖️  Rules Fired: {}", token_data.fired_rules.join(", "));
+    }
+    
+    println!("\n💰 FINANCIAL METRICS:");
+    println!("  • Market Cap: ${:.2}", token_data.market_cap);
+    println!("  • Price: ${:.6}", token_data.price);
+    println!("  • Supply: {:.2}", token_data.supply_amount);
+    println!("  • Total Liquidity: {:.2} SOL", token_data.liquidity_total);
+    
+    println!("\n👥 HOLDER ANALYSIS:");
+    println!("  • Total Holders: {}", token_data.holder_count);
+    println!("  • Top 10 Hold: {:.2}%", token_data.top_10_percentage);
+    
+    if !token_data.provider_scores.is_empty() {
+        println!("\n🧮 PROVIDER SCORES:");
+        for provider_score in &token_data.provider_scores {
+            println!(
+                "  • {} (weight {:.2}): {}/100 ({:?}){}",
+                provider_score.provider,
+                provider_score.weight,
+                provider_score.score,
+                provider_score.risk_level,
+                if provider_score.is_honeypot { " 🍯 honeypot" } else { "" }
+            );
         }
+    }
 
-        // Check high concentration of top holders
-        let (top_10_percentage, _) = self.calculate_holder_metrics(token_data)?;
-        if top_10_percentage > 80.0 {
-            honeypot_indicators += 1;
+    println!("\n🛡️  SECURITY STATUS:");
+    println!("  • Mint Authority: {}", if token_data.mint_disabled { "✅ Disabled" } else { "⚠️  Active" });
+    println!("  • Freeze Authority: {}", if token_data.freeze_disabled { "✅ Disabled" } else { "⚠️  Active" });
+    println!("  • LP Burned: {}", if token_data.lp_burned { "✅ Yes" } else { "⚠️  No" });
+    
+    if let Some(onchain) = &token_data.onchain {
+        println!("\n⛓️  ON-CHAIN MINT ({}):", onchain.program);
+        println!("  • Mint Authority: {}", onchain.mint_authority.as_deref().unwrap_or("none"));
+        println!("  • Freeze Authority: {}", onchain.freeze_authority.as_deref().unwrap_or("none"));
+        println!("  • Supply: {:.2} ({} decimals)", onchain.supply_amount(), onchain.decimals);
+        for extension in &onchain.extensions {
+            println!("  • Extension: {} ({:?})", extension.describe(), extension.severity());
         }
-
-        // Check for high risk count
-        if let Some(indicator_data) = token_data.get("indicatorData") {
-            if let Some(high_risks) = indicator_data.get("high") {
-                if let Some(count) = high_risks.get("count").and_then(|v| v.as_u64()) {
-                    if count >= 3 {
-                        honeypot_indicators += 1;
-                    }
-                }
+        if let Some(holders) = &onchain.holders {
+            println!(
+                "  • Top Holder: {:.2}%, Top 10: {:.2}% ({} pool accounts excluded)",
+                holders.top_1_percentage, holders.top_10_percentage, holders.excluded_accounts
+            );
+            if let Some(holder_count) = holders.holder_count {
+                println!("  • Holders: {}", holder_count);
             }
         }
-
-        // If 3 or more honeypot indicators, classify as honeypot
-        Ok(honeypot_indicators >= 3)
+        if !onchain.discrepancies.is_empty() {
+            println!("  • ⚠️  {} disagreement(s) with the risk report", onchain.discrepancies.len());
+        }
     }
 
-    pub fn print_analysis(&self, token_data: &TokenData) {
-        println!("\n{} ===== TOKEN SECURITY ANALYSIS =====", token_data.risk_level.as_emoji());
-        println!("📍 Token: {} ({})", token_data.token_name, token_data.token_symbol);
-        println!("🔗 Address: {}", token_data.address);
-        println!("📊 Risk Score: {}/100 ({})", token_data.score, format!("{:?}", token_data.risk_level));
-        println!("🍯 Honeypot Risk: {}", if token_data.is_honeypot { "⚠️  HIGH" } else { "✅ LOW" });
-        
-        println!("\n💰 FINANCIAL METRICS:");
-        println!("  • Market Cap: ${:.2}", token_data.market_cap);
-        println!("  • Price: ${:.6}", token_data.price);
-        println!("  • Supply: {:.2}", token_data.supply_amount);
-        println!("  • Total Liquidity: {:.2} SOL", token_data.liquidity_total);
-        
-        println!("\n👥 HOLDER ANALYSIS:");
-        println!("  • Total Holders: {}", token_data.holder_count);
-        println!("  • Top 10 Hold: {:.2}%", token_data.top_10_percentage);
-        
-        println!("\n🛡️  SECURITY STATUS:");
-        println!("  • Mint Authority: {}", if token_data.mint_disabled { "✅ Disabled" } else { "⚠️  Active" });
-        println!("  • Freeze Authority: {}", if token_data.freeze_disabled { "✅ Disabled" } else { "⚠️  Active" });
-        println!("  • LP Burned: {}", if token_data.lp_burned { "✅ Yes" } else { "⚠️  No" });
-        
-        if !token_data.audit_risks.is_empty() {
-            println!("\n⚠️  AUDIT RISKS:");
-            for risk in &token_data.audit_risks {
-                println!("  • {}", risk);
-            }
+    if !token_data.audit_risks.is_empty() {
+        println!("\n⚠️  AUDIT RISKS:");
+        for risk in &token_data.audit_risks {
+            println!("  • {}", risk);
         }
-        
-        println!("\n📅 Deploy Time: {}", token_data.deploy_time);
-        println!("=====================================\n");
     }
+    
+    println!("\n📅 Deploy Time: {}", token_data.deploy_time);
+    println!("=====================================\n");
 }
-
-pub async fn sniffer(token_address: &str ) ->Result<TokenData> {
-    println!("🚀 SolSniffer Token Analyzer v1.0");
-    let client = SolSnifferClient::new()?;    
-
-    let token_data = client.analyze_token(token_address).await?;
-
-    client.print_analysis(&token_data);
-
-    if token_data.is_honeypot {
-        println!("🚨 HONEYPOT ALERT: This token shows signs of being a honeypot!");
-    }
-    if token_data.risk_level == RiskLevel::Critical {
-        println!("🚨 CRITICAL RISK: Avoid this token!");
-    }
-
-    Ok(token_data)
-
-}
\ No newline at end of file
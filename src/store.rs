@@ -0,0 +1,336 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, params_from_iter, types::Value as SqlValue, Connection, OptionalExtension};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+
+use crate::filters::parse_risk_level;
+use crate::liquidity::Coin;
+use crate::parsing::PoolCreationEvent;
+use crate::solsniffer::RiskLevel;
+
+const DEFAULT_EVENT_DB_PATH: &str = "data/events.db";
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 500;
+// Most events sent to one resuming client from the store
+const MAX_REPLAY_EVENTS: u32 = 1_000;
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS pool_events (
+    signature          TEXT PRIMARY KEY,
+    dex_name           TEXT NOT NULL,
+    pool_type          TEXT,
+    pool_address       TEXT,
+    token_a_address    TEXT NOT NULL,
+    token_a_symbol     TEXT NOT NULL,
+    token_a_risk_level TEXT NOT NULL,
+    token_a_score      INTEGER NOT NULL,
+    token_b_address    TEXT NOT NULL,
+    token_b_symbol     TEXT NOT NULL,
+    token_b_risk_level TEXT NOT NULL,
+    token_b_score      INTEGER NOT NULL,
+    token_a_amount     TEXT,
+    token_b_amount     TEXT,
+    sol_amount         TEXT,
+    coin_type          TEXT NOT NULL,
+    risk_level         TEXT NOT NULL,
+    is_honeypot        INTEGER NOT NULL,
+    timestamp          TEXT NOT NULL,
+    block_time_ms      INTEGER NOT NULL,
+    event_json         TEXT NOT NULL,
+    -- Stream id the event was published under
+    event_id           INTEGER
+);
+CREATE INDEX IF NOT EXISTS pool_events_block_time ON pool_events (block_time_ms);
+CREATE INDEX IF NOT EXISTS pool_events_dex_name ON pool_events (dex_name);
+-- reserved_event_id: highest stream id the bus may have handed out, for
+-- every event type and not just the pools stored above
+CREATE TABLE IF NOT EXISTS stream_meta (
+    key   TEXT PRIMARY KEY,
+    value INTEGER NOT NULL
+);
+"#;
+
+// Filters for GET /pools; every field is optional and they combine with AND
+#[derive(Debug, Default, Deserialize)]
+pub struct PoolQuery {
+    pub dex: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub coin_type: Option<Coin>,
+    // Worst risk level of the pool's two tokens
+    #[serde(default, deserialize_with = "deserialize_risk_level")]
+    pub risk_level: Option<RiskLevel>,
+    pub honeypot: Option<bool>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+// Accepts the same spellings as the max_risk filter of /stream
+fn deserialize_risk_level<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<RiskLevel>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| parse_risk_level(&value).map_err(|e| D::Error::custom(format!("Invalid risk_level {}", e))))
+        .transpose()
+}
+
+#[derive(Debug, Serialize)]
+pub struct PoolPage {
+    pub total: u64,
+    pub limit: u32,
+    pub offset: u32,
+    pub pools: Vec<PoolCreationEvent>,
+}
+
+// Embedded SQLite history of every pool creation we published. The full event
+// is kept as JSON; the columns beside it exist for filtering.
+#[derive(Clone)]
+pub struct EventStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl EventStore {
+    // EVENT_DB_PATH overrides the default data/events.db
+    pub fn from_env() -> Result<Self> {
+        let path = std::env::var("EVENT_DB_PATH")
+            .unwrap_or_else(|_| DEFAULT_EVENT_DB_PATH.to_string());
+        Self::open(&path)
+    }
+
+    pub fn open(path: &str) -> Result<Self> {
+        if let Some(parent) = Path::new(path).parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create event store directory for '{}'", path))?;
+        }
+
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open event store '{}'", path))?;
+        conn.execute_batch(SCHEMA)
+            .and_then(|_| migrate(&conn))
+            .with_context(|| format!("Failed to initialise event store '{}'", path))?;
+
+        println!("🗄️  Event store ready at {}", path);
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    // Inserts the event, or replaces the stored copy of the same transaction
+    pub async fn save_pool_event(&self, pool_event: &PoolCreationEvent, event_id: u64) -> Result<()> {
+        let pool_event = pool_event.clone();
+        self.with_conn(move |conn| {
+            let risk_level = pool_event.risk_level();
+            let is_honeypot = pool_event.is_honeypot();
+            let block_time_ms = DateTime::parse_from_rfc3339(&pool_event.timestamp)
+                .map(|time| time.timestamp_millis())
+                .unwrap_or_else(|_| Utc::now().timestamp_millis());
+
+            conn.execute(
+                "INSERT OR REPLACE INTO pool_events (
+                    signature, dex_name, pool_type, pool_address,
+                    token_a_address, token_a_symbol, token_a_risk_level, token_a_score,
+                    token_b_address, token_b_symbol, token_b_risk_level, token_b_score,
+                    token_a_amount, token_b_amount, sol_amount,
+                    coin_type, risk_level, is_honeypot, timestamp, block_time_ms, event_json, event_id
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)",
+                params![
+                    pool_event.transaction_signature,
+                    pool_event.dex_name,
+                    pool_event.pool_type,
+                    pool_event.pool_address,
+                    pool_event.token_a.address,
+                    pool_event.token_a.token_symbol,
+                    format!("{:?}", pool_event.token_a.risk_level),
+                    pool_event.token_a.score,
+                    pool_event.token_b.address,
+                    pool_event.token_b.token_symbol,
+                    format!("{:?}", pool_event.token_b.risk_level),
+                    pool_event.token_b.score,
+                    pool_event.liquidity_amounts.token_a_amount,
+                    pool_event.liquidity_amounts.token_b_amount,
+                    pool_event.liquidity_amounts.sol_amount,
+                    pool_event.coin_type.to_string(),
+                    format!("{:?}", risk_level),
+                    is_honeypot,
+                    pool_event.timestamp,
+                    block_time_ms,
+                    serde_json::to_string(&pool_event)?,
+                    event_id as i64,
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    // Newest first
+    pub async fn query_pools(&self, query: PoolQuery) -> Result<PoolPage> {
+        self.with_conn(move |conn| {
+            let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+            let offset = query.offset.unwrap_or(0);
+
+            let mut conditions: Vec<&str> = Vec::new();
+            let mut values: Vec<SqlValue> = Vec::new();
+
+            if let Some(dex) = query.dex {
+                conditions.push("dex_name = ? COLLATE NOCASE");
+                values.push(SqlValue::Text(dex));
+            }
+            if let Some(from) = query.from {
+                conditions.push("block_time_ms >= ?");
+                values.push(SqlValue::Integer(from.timestamp_millis()));
+            }
+            if let Some(to) = query.to {
+                conditions.push("block_time_ms <= ?");
+                values.push(SqlValue::Integer(to.timestamp_millis()));
+            }
+            if let Some(coin_type) = query.coin_type {
+                conditions.push("coin_type = ? COLLATE NOCASE");
+                values.push(SqlValue::Text(coin_type.to_string()));
+            }
+            if let Some(risk_level) = query.risk_level {
+                conditions.push("risk_level = ?");
+                values.push(SqlValue::Text(format!("{:?}", risk_level)));
+            }
+            if let Some(honeypot) = query.honeypot {
+                conditions.push("is_honeypot = ?");
+                values.push(SqlValue::Integer(honeypot as i64));
+            }
+
+            let where_clause = if conditions.is_empty() {
+                String::new()
+            } else {
+                format!("WHERE {}", conditions.join(" AND "))
+            };
+
+            let total: u64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM pool_events {}", where_clause),
+                params_from_iter(values.iter()),
+                |row| row.get(0),
+            )?;
+
+            values.push(SqlValue::Integer(limit as i64));
+            values.push(SqlValue::Integer(offset as i64));
+
+            let mut statement = conn.prepare(&format!(
+                "SELECT event_json FROM pool_events {} ORDER BY block_time_ms DESC, signature LIMIT ? OFFSET ?",
+                where_clause
+            ))?;
+            let pools = statement
+                .query_map(params_from_iter(values.iter()), |row| row.get::<_, String>(0))?
+                .map(|json| Ok(serde_json::from_str(&json?)?))
+                .collect::<Result<Vec<PoolCreationEvent>>>()?;
+
+            Ok(PoolPage {
+                total,
+                limit,
+                offset,
+                pools,
+            })
+        })
+        .await
+    }
+
+    pub async fn get_pool(&self, signature: String) -> Result<Option<PoolCreationEvent>> {
+        self.with_conn(move |conn| {
+            let json: Option<String> = conn
+                .query_row(
+                    "SELECT event_json FROM pool_events WHERE signature = ?1",
+                    params![signature],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            json.map(|json| Ok(serde_json::from_str(&json)?)).transpose()
+        })
+        .await
+    }
+
+    // Highest stream id handed out before this run, so the bus can continue from it
+    pub async fn last_event_id(&self) -> Result<u64> {
+        self.with_conn(|conn| {
+            let last_id: Option<i64> = conn.query_row(
+                "SELECT MAX(id) FROM (
+                    SELECT MAX(event_id) AS id FROM pool_events
+                    UNION ALL
+                    SELECT value FROM stream_meta WHERE key = 'reserved_event_id'
+                )",
+                [],
+                |row| row.get(0),
+            )?;
+            Ok(last_id.unwrap_or(0) as u64)
+        })
+        .await
+    }
+
+    // Records that the bus may hand out ids up to `up_to`
+    pub async fn reserve_event_ids(&self, up_to: u64) -> Result<()> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO stream_meta (key, value) VALUES ('reserved_event_id', ?1)
+                 ON CONFLICT(key) DO UPDATE SET value = MAX(value, excluded.value)",
+                params![up_to as i64],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    // Stored pool events with after < id < before, oldest first, for clients
+    // resuming from an id the in-memory buffer no longer holds
+    pub async fn pool_events_between(
+        &self,
+        after: u64,
+        before: u64,
+    ) -> Result<Vec<(u64, PoolCreationEvent)>> {
+        self.with_conn(move |conn| {
+            let mut statement = conn.prepare(
+                "SELECT event_id, event_json FROM pool_events
+                 WHERE event_id > ?1 AND event_id < ?2
+                 ORDER BY event_id LIMIT ?3",
+            )?;
+            let events = statement
+                .query_map(params![after as i64, before as i64, MAX_REPLAY_EVENTS], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                })?
+                .map(|row| {
+                    let (event_id, json) = row?;
+                    Ok((event_id as u64, serde_json::from_str(&json)?))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(events)
+        })
+        .await
+    }
+
+    // SQLite calls block, so they run off the async executor
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().map_err(|_| anyhow!("Event store lock poisoned"))?;
+            f(&conn)
+        })
+        .await?
+    }
+}
+
+// Brings stores created by older versions up to the current schema
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    let has_event_id = conn
+        .prepare("SELECT 1 FROM pragma_table_info('pool_events') WHERE name = 'event_id'")?
+        .exists([])?;
+    if !has_event_id {
+        conn.execute_batch("ALTER TABLE pool_events ADD COLUMN event_id INTEGER")?;
+    }
+
+    conn.execute_batch("CREATE INDEX IF NOT EXISTS pool_events_event_id ON pool_events (event_id)")
+}
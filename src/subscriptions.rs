@@ -1,48 +1,21 @@
-// Raydium
-pub const RAYDIUM_SUBSCRIPTION: &str = r#"
-subscription{
+use crate::dexes::DexRegistry;
+
+// Combined subscription over every configured DEX; the filter is filled in
+// from config/dexes.toml by `combined_subscription`.
+const COMBINED_TEMPLATE: &str = r#"
+subscription {
   Solana {
     Instructions(
-      where: {
-        Transaction: {
-          Result: {
-            Success: true
-          }
-        },
-        Instruction: {
-          Program: {
-            Method: {
-              is: "initialize2"
-            },
-            Address: {
-              is: "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8"
-            }
-          }
-        }
-      }
+      where: {any: __PROGRAM_FILTERS__}
     ) {
       Block {
         Time
       }
-      Transaction {
-        Signature
-      }
       Instruction {
-        Accounts {
-          Address
-          IsWritable
-          Token {
-            Mint
-            Owner
-            ProgramId
-          }
-        }
-        Logs
         Program {
-          Method
           Name
+          Method
           Arguments {
-            Type
             Name
             Value {
               __typename
@@ -75,284 +48,72 @@ subscription{
           AccountNames
           Address
         }
-      }
-    }
-  }
-}
-"#;
-
-
-
-// Pumpswap
-pub const PUMPSWAP_SUBSCRIPTION: &str = r#"
-subscription {
-  Solana {
-    Instructions(
-      where: {Instruction: {Program: {Method: {is: "create_pool"}, Address: {is: "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA"}}}}
-    ) {
-      Instruction {
-        Program {
-          Address
-          Name
-          Method
-          Arguments {
-            Name
-            Type
-            Value {
-              ... on Solana_ABI_Json_Value_Arg {
-                json
-              }
-              ... on Solana_ABI_Float_Value_Arg {
-                float
-              }
-              ... on Solana_ABI_Boolean_Value_Arg {
-                bool
-              }
-              ... on Solana_ABI_Bytes_Value_Arg {
-                hex
-              }
-              ... on Solana_ABI_BigInt_Value_Arg {
-                bigInteger
-              }
-              ... on Solana_ABI_Address_Value_Arg {
-                address
-              }
-              ... on Solana_ABI_String_Value_Arg {
-                string
-              }
-              ... on Solana_ABI_Integer_Value_Arg {
-                integer
-              }
-            }
-          }
-          AccountNames
-          Json
-        }
         Accounts {
           Address
-          IsWritable
-          Token {
-            Mint
-            Owner
-            ProgramId
-          }
         }
-        Logs
-        BalanceUpdatesCount
-        AncestorIndexes
-        CallPath
-        CallerIndex
-        Data
-        Depth
-        ExternalSeqNumber
-        Index
-        InternalSeqNumber
-        TokenBalanceUpdatesCount
       }
       Transaction {
-        Fee
-        FeeInUSD
         Signature
-        Signer
-        FeePayer
-        Result {
-          Success
-          ErrorMessage
-        }
-      }
-      Block {
-        Time
-      }
-    }
-  }
-}
-"#;
-
-
-// Meteora
-pub const METEORA_SUBSCRIPTION: &str = r#"
-subscription MyQuery {
-  Solana {
-    Instructions(
-      where: {Transaction: {Result: {Success: true}}, Instruction: {Program: {Method: {is: "initializePermissionlessConstantProductPoolWithConfig2"}, Address: {is: "Eo7WjKq67rjJQSZxS6z3YkapzY3eMj6Xy8X5EQVn5UaB"}}}}
-    ) {
-      Block {
-        Time
-      }
-      Instruction {
-        Accounts {
-          Address
-          IsWritable
-          Token {
-            Mint
-            Owner
-            ProgramId
-          }
-        }
-        Program {
-          AccountNames
-          Address
-          Arguments {
-            Name
-            Type
-            Value {
-              ... on Solana_ABI_Integer_Value_Arg {
-                integer
-              }
-              ... on Solana_ABI_String_Value_Arg {
-                string
-              }
-              ... on Solana_ABI_Address_Value_Arg {
-                address
-              }
-              ... on Solana_ABI_BigInt_Value_Arg {
-                bigInteger
-              }
-              ... on Solana_ABI_Bytes_Value_Arg {
-                hex
-              }
-              ... on Solana_ABI_Boolean_Value_Arg {
-                bool
-              }
-              ... on Solana_ABI_Float_Value_Arg {
-                float
-              }
-              ... on Solana_ABI_Json_Value_Arg {
-                json
-              }
-            }
-          }
-          Method
-          Name
-        }
-      }
-      Transaction {
-        Signature
-        Signer
       }
     }
   }
 }
 "#;
 
+// One clause per program listing only its own methods, so a method name of
+// one DEX never matches on another DEX's program
+pub fn combined_subscription(registry: &DexRegistry) -> String {
+    let quote_list = |items: &[&str]| {
+        let quoted: Vec<String> = items.iter().map(|item| format!("\"{}\"", item)).collect();
+        format!("[{}]", quoted.join(", "))
+    };
 
+    let filters: Vec<String> = registry
+        .program_methods()
+        .iter()
+        .map(|(program_id, methods)| {
+            format!(
+                "{{Instruction: {{Program: {{Address: {{is: \"{}\"}}, Method: {{in: {}}}}}}}}}",
+                program_id,
+                quote_list(methods)
+            )
+        })
+        .collect();
 
-// Orca 
+    COMBINED_TEMPLATE.replace("__PROGRAM_FILTERS__", &format!("[{}]", filters.join(", ")))
+}
 
-// pub const ORCA_SUBSCRIPTION: &str = r#"
-// subscription {
-//   Solana {
-//     Instructions(
-//       where: {
-//         Instruction: {
-//           Program: {
-//             Method: {in: ["initializePool", "initializePoolV2"]}
-//             Address: { is: "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc" }
-//           }
-//         }
-//       }
-//     ) {
-//       Instruction {
-//         Program {
-//           Method
-//           Arguments {
-//             Name
-//             Value {
-//               __typename
-//               ... on Solana_ABI_Integer_Value_Arg {
-//                 integer
-//               }
-//               ... on Solana_ABI_String_Value_Arg {
-//                 string
-//               }
-//               ... on Solana_ABI_Address_Value_Arg {
-//                 address
-//               }
-//               ... on Solana_ABI_BigInt_Value_Arg {
-//                 bigInteger
-//               }
-//               ... on Solana_ABI_Bytes_Value_Arg {
-//                 hex
-//               }
-//               ... on Solana_ABI_Boolean_Value_Arg {
-//                 bool
-//               }
-//               ... on Solana_ABI_Float_Value_Arg {
-//                 float
-//               }
-//               ... on Solana_ABI_Json_Value_Arg {
-//                 json
-//               }
-//             }
-//           }
-//         }
-//         Accounts {
-//           Address
-//         }
-//       }
-//       Transaction {
-//         Signature
-//       }
-//     }
-//   }
-// }
-// "#;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    #[test]
+    fn each_program_is_filtered_on_its_own_methods() {
+        let registry = DexRegistry::load("config/dexes.toml").expect("shipped DEX config");
+        let query = combined_subscription(&registry);
+        let filter = query
+            .lines()
+            .find(|line| line.trim_start().starts_with("where:"))
+            .expect("where clause");
 
-pub const COMBINED: &str = r#"
-subscription {
-  Solana {
-    Instructions(
-      where: {Instruction: {Program: {Method: {in: ["initialize2", "create_pool", "initializePermissionlessConstantProductPoolWithConfig2"]}, Address: {in: ["675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8", "pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn52FMfXEA", "Eo7WjKq67rjJQSZxS6z3YkapzY3eMj6Xy8X5EQVn5UaB"]}}}}
-    ) {
-      Block {
-        Time
-      }
-      Instruction {
-        Program {
-          Name
-          Method
-          Arguments {
-            Name
-            Value {
-              __typename
-              ... on Solana_ABI_Integer_Value_Arg {
-                integer
-              }
-              ... on Solana_ABI_String_Value_Arg {
-                string
-              }
-              ... on Solana_ABI_Address_Value_Arg {
-                address
-              }
-              ... on Solana_ABI_BigInt_Value_Arg {
-                bigInteger
-              }
-              ... on Solana_ABI_Bytes_Value_Arg {
-                hex
-              }
-              ... on Solana_ABI_Boolean_Value_Arg {
-                bool
-              }
-              ... on Solana_ABI_Float_Value_Arg {
-                float
-              }
-              ... on Solana_ABI_Json_Value_Arg {
-                json
-              }
+        let clauses: Vec<&str> = filter.split("{Instruction:").skip(1).collect();
+        assert_eq!(clauses.len(), registry.program_methods().len());
+        for (clause, (program_id, methods)) in clauses.iter().zip(registry.program_methods()) {
+            assert!(clause.contains(&format!("Address: {{is: \"{}\"}}", program_id)));
+            for method in &methods {
+                assert!(clause.contains(&format!("\"{}\"", method)));
             }
-          }
-          AccountNames
-          Address
         }
-        Accounts {
-          Address
+
+        // A Raydium AMM method is not accepted on the Meteora DLMM program
+        let dlmm = registry.dexes.iter().find(|dex| dex.id == "meteora_dlmm").expect("DLMM");
+        let dlmm_clause = clauses
+            .iter()
+            .find(|clause| clause.contains(&dlmm.program_id))
+            .expect("DLMM clause");
+        let raydium = registry.dexes.iter().find(|dex| dex.id == "raydium_amm").expect("Raydium");
+        for method in &raydium.methods {
+            assert!(!dlmm_clause.contains(&format!("\"{}\"", method)));
         }
-      }
-      Transaction {
-        Signature
-      }
     }
-  }
 }
-"#;